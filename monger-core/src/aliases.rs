@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    error::{Error, Result},
+    spec::VersionSpec,
+    Monger,
+};
+
+/// Checks that a name can be used as an alias, returning the reason if it can't. Aliases can't
+/// look like versions, since they'd be ambiguous with them.
+pub(crate) fn check_alias_name(name: &str) -> Option<&'static str> {
+    if name.is_empty() || name.starts_with('.') || name.contains(|c: char| c.is_whitespace()) {
+        return Some("aliases can't be empty, start with a '.', or contain whitespace");
+    }
+
+    match VersionSpec::parse(name) {
+        Some(VersionSpec::Constraint(_)) => {
+            Some("aliases can't look like a version constraint (e.g. `6` or `>=4.4`)")
+        }
+        Some(_) => Some("aliases can't look like a version"),
+        None if name == "system" => Some("aliases can't look like a version"),
+        None => None,
+    }
+}
+
+impl Monger {
+    /// Creates or replaces an alias for an installed version, which can then be used anywhere a
+    /// version ID is accepted. Returns the ID the alias refers to.
+    pub fn create_alias(&mut self, name: &str, id: &str) -> Result<String> {
+        let invalid = |reason: &str| Error::InvalidAlias {
+            name: name.into(),
+            reason: reason.into(),
+        };
+
+        if let Some(reason) = check_alias_name(name) {
+            return Err(invalid(reason));
+        }
+
+        if self.fs.version_exists(name) {
+            return Err(invalid("an installed version already has that ID"));
+        }
+
+        // Aliases to other aliases are stored as the ID they refer to, so that resolving an alias
+        // never takes more than one step.
+        let target = self.fs.resolve_alias(id).to_string();
+
+        if target == name {
+            return Err(invalid("an alias can't refer to itself"));
+        }
+
+        self.fs
+            .get_newest_matching_version(&target)
+            .map_err(|_| Error::VersionNotFound { version: id.into() })?;
+
+        self.config.aliases.insert(name.into(), target.clone());
+        self.save_aliases()?;
+
+        Ok(target)
+    }
+
+    pub fn delete_alias(&mut self, name: &str) -> Result<()> {
+        if self.config.aliases.remove(name).is_none() {
+            return Err(Error::AliasNotFound { name: name.into() });
+        }
+
+        self.save_aliases()
+    }
+
+    pub fn list_aliases(&self) -> &BTreeMap<String, String> {
+        &self.config.aliases
+    }
+
+    /// The aliases that currently refer to an installed version, including those for a major and
+    /// minor version that it is the newest installed release of.
+    pub fn aliases_of(&self, version: &str) -> Vec<&str> {
+        self.config
+            .aliases
+            .iter()
+            .filter(|(_, target)| {
+                self.fs.get_newest_matching_version(target).ok().as_deref() == Some(version)
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// The version used when none is given and none is pinned by `$MONGER_VERSION` or the current
+    /// project.
+    pub fn default_version(&self) -> Option<&str> {
+        self.config.versions.default.as_deref()
+    }
+
+    /// Sets the version used when none is given and none is pinned by `$MONGER_VERSION` or the
+    /// current project. The version can be an alias, a major and minor version, or "system".
+    pub fn set_default_version(&mut self, id: &str) -> Result<()> {
+        self.fs
+            .get_newest_matching_version(id)
+            .map_err(|_| Error::VersionNotFound { version: id.into() })?;
+
+        self.config.versions.default = Some(id.into());
+        self.config.save(&self.config_path)
+    }
+
+    fn save_aliases(&mut self) -> Result<()> {
+        self.fs.set_aliases(self.config.aliases.clone());
+        self.config.save(&self.config_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_alias_name;
+
+    #[test]
+    fn alias_names() {
+        assert!(check_alias_name("prod-like").is_none());
+        assert!(check_alias_name("ci").is_none());
+        assert!(check_alias_name("6-debug").is_none());
+
+        assert!(check_alias_name("").is_some());
+        assert!(check_alias_name("4.4").is_some());
+        assert!(check_alias_name("5.0.14").is_some());
+        assert!(check_alias_name("4.4.8-rc1").is_some());
+        assert!(check_alias_name("system").is_some());
+        assert!(check_alias_name("6").unwrap().contains("constraint"));
+        assert!(check_alias_name(">=4.4").unwrap().contains("constraint"));
+        assert!(check_alias_name("my alias").is_some());
+    }
+}
@@ -0,0 +1,144 @@
+use std::{sync::Mutex, thread};
+
+use crate::{error::Result, events::Event, Installation, Monger};
+
+/// How many releases are downloaded at once when neither the caller nor `download.jobs` says.
+const DEFAULT_JOBS: usize = 4;
+
+/// Parses a list of versions to install, as read from a file passed to `monger get --from-file`.
+/// Versions are separated by whitespace, and everything after a `#` on a line is a comment.
+pub fn parse_version_list(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(str::split_whitespace)
+        .map(ToString::to_string)
+        .collect()
+}
+
+impl Monger {
+    /// Installs several versions, downloading up to `jobs` of them at once (or `download.jobs`
+    /// from the config if `None`). The versions are all resolved before anything is downloaded,
+    /// and each is installed independently, so one failing doesn't stop the others. The results
+    /// are in the same order as the versions.
+    pub fn download_mongodb_versions(
+        &self,
+        version_strs: &[&str],
+        force: bool,
+        include_prereleases: bool,
+        os: Option<&str>,
+        jobs: Option<usize>,
+    ) -> Result<Vec<Result<Installation>>> {
+        let jobs = jobs
+            .or(self.config.download.jobs)
+            .unwrap_or(DEFAULT_JOBS)
+            .clamp(1, version_strs.len().max(1));
+
+        let tasks = Mutex::new(
+            version_strs
+                .iter()
+                .zip(self.resolve_releases(version_strs, include_prereleases)?)
+                .enumerate(),
+        );
+        let results = Mutex::new(version_strs.iter().map(|_| None).collect::<Vec<_>>());
+
+        thread::scope(|scope| {
+            for _ in 0..jobs {
+                scope.spawn(|| loop {
+                    let next = tasks.lock().unwrap().next();
+                    let (i, (version_str, release)) = match next {
+                        Some(task) => task,
+                        None => break,
+                    };
+
+                    let result = release.and_then(|release| {
+                        self.install_release(version_str, release, force, os, None)
+                    });
+
+                    self.events.emit(match result {
+                        Ok(ref installation) => Event::Installed {
+                            id: installation.id.clone(),
+                            downloaded: installation.downloaded,
+                        },
+                        Err(ref e) => Event::InstallFailed {
+                            version: version_str.to_string(),
+                            error: e.to_string(),
+                        },
+                    });
+
+                    results.lock().unwrap()[i] = Some(result);
+                });
+            }
+        });
+
+        Ok(results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.expect("every version is installed by a worker"))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::create_dir_all,
+        sync::{Arc, Mutex},
+    };
+
+    use super::parse_version_list;
+    use crate::{config::Config, events::Event, util::TempDir, Monger};
+
+    #[test]
+    fn version_lists() {
+        let contents = "# versions the tests run against\n4.4.1\n\n5.0 6.0  # newest\n  latest\n";
+
+        assert_eq!(
+            vec!["4.4.1", "5.0", "6.0", "latest"],
+            parse_version_list(contents)
+        );
+        assert!(parse_version_list("\n# nothing\n").is_empty());
+    }
+
+    #[test]
+    fn independent_installs() {
+        let home_dir = TempDir::new("batch");
+
+        for id in &["4.2.9", "4.4.1"] {
+            create_dir_all(home_dir.join("mongodb-versions").join(id).join("bin")).unwrap();
+        }
+
+        // Nothing listens on the mirror, so any version that isn't installed fails to download.
+        let mut config = Config::default();
+        config.download.mirror = Some("http://127.0.0.1:1".into());
+
+        let mut monger =
+            Monger::with_config(&home_dir, home_dir.join("config.toml"), config).unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let handler_events = events.clone();
+        monger.set_event_handler(move |event| handler_events.lock().unwrap().push(event.clone()));
+
+        let results = monger
+            .download_mongodb_versions(
+                &["4.4.1", "4.4.0", "4.2.9"],
+                false,
+                false,
+                Some("ubuntu1804"),
+                Some(2),
+            )
+            .unwrap();
+
+        // The versions after the one that failed are still installed, and the results are in the
+        // order the versions were given.
+        assert_eq!(3, results.len());
+        assert_eq!("4.4.1", results[0].as_ref().unwrap().id);
+        assert!(results[1].is_err());
+        assert_eq!("4.2.9", results[2].as_ref().unwrap().id);
+
+        let events = events.lock().unwrap();
+        assert!(events.iter().any(
+            |event| matches!(event, Event::InstallFailed { version, .. } if version == "4.4.0")
+        ));
+    }
+}
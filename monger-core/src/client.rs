@@ -1,28 +1,52 @@
-use std::io::Read;
+use std::{
+    io::{ErrorKind, Read},
+    time::{Duration, Instant},
+};
 
-use reqwest::blocking::{Client, ClientBuilder, Response};
+use reqwest::{
+    blocking::{Client, ClientBuilder, Response},
+    Proxy,
+};
 
-use crate::error::{Error, Result};
+use crate::{
+    error::{Error, Result},
+    events::{Event, Events},
+};
+
+/// How often progress is reported while downloading a release.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Debug)]
 pub struct HttpClient {
     client: Client,
+    events: Events,
 }
 
 impl HttpClient {
-    pub fn new() -> Result<Self> {
+    pub fn new(proxy: Option<&str>) -> Result<Self> {
+        let mut builder = ClientBuilder::new().gzip(false);
+
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+
         Ok(Self {
-            client: ClientBuilder::new().gzip(false).build()?,
+            client: builder.build()?,
+            events: Default::default(),
         })
     }
 
+    pub(crate) fn set_events(&mut self, events: Events) {
+        self.events = events;
+    }
+
     pub fn get(&self, url: &str) -> Result<Response> {
         let response = self.client.get(url).send()?;
         Ok(response)
     }
 
     pub fn download_url(&self, url: &str) -> Result<Vec<u8>> {
-        println!("downloading {}...", url);
+        self.events.emit(Event::Downloading { url: url.into() });
         let mut data = Vec::new();
         let mut response = self.client.get(url).send()?;
 
@@ -35,8 +59,10 @@ impl HttpClient {
         Ok(data)
     }
 
+    /// Downloads a release, reporting how much of it has been downloaded every so often and once
+    /// it's finished, so that downloads of several versions at once can be told apart.
     pub fn download_version(&self, url: &str, version: &str) -> Result<Vec<u8>> {
-        println!("downloading {}...", url);
+        self.events.emit(Event::Downloading { url: url.into() });
         let mut data = Vec::new();
         let mut response = self.client.get(url).send()?;
 
@@ -46,7 +72,33 @@ impl HttpClient {
             });
         }
 
-        response.read_to_end(&mut data)?;
+        let total = response.content_length();
+        let progress = |downloaded: usize| Event::DownloadProgress {
+            version: version.into(),
+            downloaded: downloaded as u64,
+            total,
+        };
+
+        let mut buf = [0; 64 * 1024];
+        let mut reported = Instant::now();
+
+        loop {
+            let len = match response.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            data.extend_from_slice(&buf[..len]);
+
+            if reported.elapsed() >= PROGRESS_INTERVAL {
+                self.events.emit(progress(data.len()));
+                reported = Instant::now();
+            }
+        }
+
+        self.events.emit(progress(data.len()));
 
         Ok(data)
     }
@@ -58,7 +110,7 @@ mod tests {
 
     #[test]
     fn download_test() {
-        let client = HttpClient::new().unwrap();
+        let client = HttpClient::new(None).unwrap();
         let data = client
             .download_version("https://httpbin.org/robots.txt", "null")
             .unwrap();
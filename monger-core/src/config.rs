@@ -0,0 +1,411 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{create_dir_all, read_to_string, write},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use toml::{value::Table, Value};
+
+use crate::{
+    aliases::check_alias_name,
+    error::{Error, Result},
+    os::{Architecture, OS_NAMES},
+};
+
+const DEFAULT_HOME_DIR: &str = ".monger";
+const CONFIG_FILE: &str = "config.toml";
+
+/// The environment variable that overrides the directory monger stores everything in.
+pub const HOME_ENV_VAR: &str = "MONGER_HOME";
+
+/// The environment variable that overrides the location of the config file.
+pub const CONFIG_ENV_VAR: &str = "MONGER_CONFIG";
+
+/// The settings read from `~/.monger/config.toml`. Every setting is optional, and unset settings
+/// keep monger's built-in behavior.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub dirs: DirsConfig,
+    pub download: DownloadConfig,
+    pub mongod: MongodConfig,
+    pub cache: CacheConfig,
+    pub versions: VersionsConfig,
+
+    /// Names for versions that can be used anywhere a version ID is accepted.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// Where monger stores its files. Relative paths are relative to the monger home directory.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DirsConfig {
+    /// The directory that MongoDB versions are installed in.
+    pub bin: Option<String>,
+
+    /// The directory that contains the data directories of started mongods.
+    pub db: Option<String>,
+
+    /// The directory that contains the state and data of deployments.
+    pub deployments: Option<String>,
+
+    /// The directory that snapshots of data directories are saved in.
+    pub snapshots: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DownloadConfig {
+    /// A URL to download MongoDB releases from instead of `https://fastdl.mongodb.org`. It must
+    /// have the same layout.
+    pub mirror: Option<String>,
+
+    /// The URL of a proxy to send HTTP requests through.
+    pub proxy: Option<String>,
+
+    /// The OS to download releases for when none is specified, instead of detecting it.
+    pub os: Option<String>,
+
+    /// The architecture to download releases for (`x86_64` or `arm64`).
+    pub arch: Option<String>,
+
+    /// How many releases to download at once when installing several versions (4 by default).
+    pub jobs: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MongodConfig {
+    /// Arguments passed to every mongod that monger starts.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub default_args: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// The number of installed versions above which monger suggests pruning after a download.
+    pub max_versions: Option<usize>,
+
+    /// How many seconds to wait for a version locked by another monger process (e.g. while it's
+    /// being installed) before giving up. Defaults to 300.
+    pub lock_timeout: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VersionsConfig {
+    /// The version used when none is given and none is pinned by `$MONGER_VERSION` or the current
+    /// project.
+    pub default: Option<String>,
+
+    /// Whether major and minor versions and constraints can resolve to pre-releases (e.g. release
+    /// candidates) when no stable release matches better.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub prereleases: bool,
+}
+
+/// The directory monger stores everything in: `$MONGER_HOME` if set, or else `~/.monger`.
+pub(crate) fn home_dir() -> Result<PathBuf> {
+    if let Some(home) = env::var_os(HOME_ENV_VAR) {
+        return Ok(home.into());
+    }
+
+    dirs::home_dir()
+        .map(|home| home.join(DEFAULT_HOME_DIR))
+        .ok_or(Error::UnknownHomeDirectory)
+}
+
+/// The location of the config file: `$MONGER_CONFIG` if set, or else `config.toml` in the monger
+/// home directory.
+pub fn config_path() -> Result<PathBuf> {
+    match env::var_os(CONFIG_ENV_VAR) {
+        Some(path) => Ok(path.into()),
+        None => Ok(home_dir()?.join(CONFIG_FILE)),
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidConfig {
+        message: message.into(),
+    }
+}
+
+fn unknown_setting(key: &str) -> Error {
+    invalid(format!("unknown setting '{}'", key))
+}
+
+fn split_key(key: &str) -> Option<(&str, &str)> {
+    let i = key.find('.')?;
+
+    Some((&key[..i], &key[i + 1..]))
+}
+
+/// Parses a value given on the command line as TOML, falling back to a plain string so that
+/// e.g. URLs don't need to be quoted.
+fn parse_value(value: &str) -> Value {
+    toml::from_str::<Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(value.into()))
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn flatten(prefix: &str, table: &Table, entries: &mut Vec<(String, String)>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match value {
+            Value::Table(table) => flatten(&key, table, entries),
+            value => entries.push((key, display_value(value))),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file, using the default settings if it doesn't exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = match read_to_string(path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(Default::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let config: Self =
+            toml::from_str(&contents).map_err(|e| invalid(format!("{}: {}", path.display(), e)))?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+
+        write(path, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(ref os) = self.download.os {
+            if !OS_NAMES.contains(&os.as_str()) {
+                return Err(invalid(format!(
+                    "download.os must be one of {}",
+                    OS_NAMES.join(", ")
+                )));
+            }
+        }
+
+        if let Some(ref arch) = self.download.arch {
+            if Architecture::from_name(arch).is_none() {
+                return Err(invalid("download.arch must be either x86_64 or arm64"));
+            }
+        }
+
+        for name in self.aliases.keys() {
+            if let Some(reason) = check_alias_name(name) {
+                return Err(invalid(format!("alias '{}': {}", name, reason)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn to_table(&self) -> Result<Table> {
+        Ok(toml::from_str(&toml::to_string(self)?)?)
+    }
+
+    /// Lists every setting that is set as a dotted key (e.g. `download.mirror`) and its value.
+    pub fn list(&self) -> Result<Vec<(String, String)>> {
+        let mut entries = Vec::new();
+        flatten("", &self.to_table()?, &mut entries);
+
+        Ok(entries)
+    }
+
+    /// Gets the value of a setting by its dotted key, or `None` if it isn't set.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        self.check_key(key)?;
+
+        Ok(self
+            .list()?
+            .into_iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value))
+    }
+
+    /// Sets a setting by its dotted key. The value is parsed as TOML if possible (e.g. numbers or
+    /// arrays) and as a string otherwise.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        self.check_key(key)?;
+
+        // Aliases have to refer to an installed version, which only `monger alias create` checks.
+        if key.starts_with("aliases.") {
+            return Err(invalid(format!(
+                "{}: aliases are created with `monger alias create`",
+                key
+            )));
+        }
+
+        // Values like "4.4" are valid TOML numbers, but are meant as strings for settings that
+        // take a version.
+        let config = match self.with_value(key, parse_value(value)) {
+            Err(_) => self.with_value(key, Value::String(value.into())),
+            config => config,
+        }?;
+        config.validate()?;
+
+        *self = config;
+
+        Ok(())
+    }
+
+    fn with_value(&self, key: &str, value: Value) -> Result<Self> {
+        let (section, field) = split_key(key).ok_or_else(|| unknown_setting(key))?;
+        let mut table = self.to_table()?;
+
+        if let Value::Table(section) = table
+            .entry(section)
+            .or_insert_with(|| Value::Table(Table::new()))
+        {
+            section.insert(field.into(), value);
+        }
+
+        Value::Table(table)
+            .try_into()
+            .map_err(|e| invalid(format!("{}: {}", key, e)))
+    }
+
+    /// Restores a setting to its default by its dotted key.
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        self.check_key(key)?;
+
+        let (section, field) = split_key(key).ok_or_else(|| unknown_setting(key))?;
+        let mut table = self.to_table()?;
+
+        if let Some(Value::Table(section)) = table.get_mut(section) {
+            section.remove(field);
+        }
+
+        *self = Value::Table(table).try_into()?;
+
+        Ok(())
+    }
+
+    /// Fails unless the key names a setting. Settings that are unset aren't serialized, so they
+    /// can't be looked up in the serialized config.
+    fn check_key(&self, key: &str) -> Result<()> {
+        let (section, field) = split_key(key).ok_or_else(|| unknown_setting(key))?;
+
+        let known = match section {
+            "dirs" => ["bin", "db", "deployments", "snapshots"].contains(&field),
+            "download" => ["mirror", "proxy", "os", "arch", "jobs"].contains(&field),
+            "mongod" => field == "default_args",
+            "cache" => ["max_versions", "lock_timeout"].contains(&field),
+            "versions" => ["default", "prereleases"].contains(&field),
+            "aliases" => !field.is_empty(),
+            _ => false,
+        };
+
+        if known {
+            Ok(())
+        } else {
+            Err(unknown_setting(key))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    #[test]
+    fn set_and_get() {
+        let mut config = Config::default();
+
+        config
+            .set("download.mirror", "https://mirror.example.com/mongodb")
+            .unwrap();
+        config.set("cache.max_versions", "5").unwrap();
+        config
+            .set("mongod.default_args", r#"["--setParameter", "a=b c"]"#)
+            .unwrap();
+
+        assert_eq!(
+            Some("https://mirror.example.com/mongodb".to_string()),
+            config.download.mirror
+        );
+        assert_eq!(Some(5), config.cache.max_versions);
+        assert_eq!(vec!["--setParameter", "a=b c"], config.mongod.default_args);
+        assert_eq!(
+            Some("5".to_string()),
+            config.get("cache.max_versions").unwrap()
+        );
+        assert_eq!(None, config.get("download.proxy").unwrap());
+
+        config.set("versions.default", "4.4").unwrap();
+        assert_eq!(Some("4.4".to_string()), config.versions.default);
+
+        config.unset("cache.max_versions").unwrap();
+        assert_eq!(None, config.cache.max_versions);
+    }
+
+    #[test]
+    fn reject_invalid_settings() {
+        let mut config = Config::default();
+
+        assert!(config.set("download.mirrors", "x").is_err());
+        assert!(config.set("cache.max_versions", "lots").is_err());
+        assert!(config.set("download.os", "beos").is_err());
+        assert!(config.set("aliases.prod-like", "5.0.14").is_err());
+        assert!(config.get("mirror").is_err());
+        assert_eq!(Config::default(), config);
+    }
+
+    #[test]
+    fn reject_invalid_alias_names() {
+        let config = |aliases: &str| toml::from_str::<Config>(aliases).unwrap().validate();
+
+        assert!(config("[aliases]\nprod-like = \"5.0.14\"\n").is_ok());
+        assert!(config("[aliases]\n\"4.4\" = \"5.0.14\"\n").is_err());
+        assert!(config("[aliases]\nsystem = \"5.0.14\"\n").is_err());
+    }
+
+    #[test]
+    fn list() {
+        let config: Config = toml::from_str(
+            r#"
+            [dirs]
+            bin = "/opt/mongodb"
+
+            [download]
+            arch = "arm64"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                ("dirs.bin".to_string(), "/opt/mongodb".to_string()),
+                ("download.arch".to_string(), "arm64".to_string()),
+            ],
+            config.list().unwrap()
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use std::{collections::BTreeMap, ffi::OsString};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, Monger};
+
+/// The default arguments used when starting a mongod, stored as lists so that arguments containing
+/// whitespace are kept intact.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct DefaultArgs {
+    /// The arguments used for every version.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub global: Vec<String>,
+
+    /// The arguments used for a single major.minor release (e.g. "4.4") or version ID (e.g.
+    /// "4.4.1"), keyed by the release or ID.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub versions: BTreeMap<String, Vec<String>>,
+}
+
+impl DefaultArgs {
+    /// The arguments set for the given release or version ID, or the global arguments if no scope
+    /// is given.
+    pub fn get(&self, scope: Option<&str>) -> Option<&Vec<String>> {
+        match scope {
+            Some(scope) => self.versions.get(scope),
+            None if self.global.is_empty() => None,
+            None => Some(&self.global),
+        }
+    }
+
+    fn set(&mut self, scope: Option<&str>, args: Vec<String>) {
+        match scope {
+            Some(scope) => {
+                self.versions.insert(scope.into(), args);
+            }
+            None => self.global = args,
+        }
+    }
+
+    fn clear(&mut self, scope: Option<&str>) -> bool {
+        match scope {
+            Some(scope) => self.versions.remove(scope).is_some(),
+            None => !std::mem::take(&mut self.global).is_empty(),
+        }
+    }
+}
+
+/// The name that an option is identified by when deciding whether a more specific scope overrides
+/// it, e.g. `--port` for both `--port 27018` and `--port=27018`. Since `--setParameter` is passed
+/// once per parameter, the parameter name is included for it.
+fn option_key(option: &[String]) -> Option<String> {
+    let first = option.first()?;
+
+    if !first.starts_with('-') {
+        return None;
+    }
+
+    let (name, value) = match first.find('=') {
+        Some(i) => (&first[..i], Some(&first[i + 1..])),
+        None => (first.as_str(), option.get(1).map(String::as_str)),
+    };
+
+    if name == "--setParameter" {
+        let parameter = value.map(|value| value.split('=').next().unwrap_or_default());
+        return Some(format!("{} {}", name, parameter.unwrap_or_default()));
+    }
+
+    Some(name.into())
+}
+
+/// Splits arguments into options, each of which is a flag followed by its values.
+fn split_options(args: &[String]) -> Vec<Vec<String>> {
+    let mut options: Vec<Vec<String>> = Vec::new();
+
+    for arg in args {
+        match options.last_mut() {
+            Some(option) if !arg.starts_with('-') => option.push(arg.clone()),
+            _ => options.push(vec![arg.clone()]),
+        }
+    }
+
+    options
+}
+
+/// Combines layers of default arguments, from least to most specific. An option in a more specific
+/// layer replaces the same option in a less specific one, and options that are passed explicitly
+/// are left out entirely.
+fn merge_args(layers: &[&[String]], explicit: &[OsString]) -> Vec<String> {
+    let explicit_keys: Vec<_> = split_options(
+        &explicit
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>(),
+    )
+    .iter()
+    .filter_map(|option| option_key(option))
+    .collect();
+
+    let mut merged: Vec<Vec<String>> = Vec::new();
+
+    for layer in layers {
+        for option in split_options(layer) {
+            let key = option_key(&option);
+            let existing = key.as_ref().and_then(|key| {
+                merged
+                    .iter()
+                    .position(|o| option_key(o).as_ref() == Some(key))
+            });
+
+            match existing {
+                Some(i) => merged[i] = option,
+                None => merged.push(option),
+            }
+        }
+    }
+
+    merged
+        .into_iter()
+        .filter(|option| option_key(option).is_none_or(|key| !explicit_keys.contains(&key)))
+        .flatten()
+        .collect()
+}
+
+impl Monger {
+    /// Deletes the default arguments set for a release or version ID, or the global default
+    /// arguments if no scope is given. Returns whether any were set.
+    pub fn clear_default_args(&self, scope: Option<&str>) -> Result<bool> {
+        let mut defaults = self.fs.read_default_args()?;
+        let cleared = defaults.clear(scope);
+
+        if cleared {
+            self.fs.write_default_args(&defaults)?;
+        }
+
+        Ok(cleared)
+    }
+
+    pub fn get_default_args(&self, scope: Option<&str>) -> Result<Option<Vec<String>>> {
+        Ok(self.fs.read_default_args()?.get(scope).cloned())
+    }
+
+    pub fn list_default_args(&self) -> Result<DefaultArgs> {
+        self.fs.read_default_args()
+    }
+
+    /// Sets the default arguments for a release (e.g. "4.4") or version ID, or the global default
+    /// arguments if no scope is given.
+    pub fn set_default_args(&self, scope: Option<&str>, args: Vec<String>) -> Result<()> {
+        let mut defaults = self.fs.read_default_args()?;
+        defaults.set(scope, args);
+
+        self.fs.write_default_args(&defaults)
+    }
+
+    /// The default arguments that a mongod of the given version is started with. Arguments set for
+    /// the version ID take precedence over those set for its major.minor release, which take
+    /// precedence over the global ones and then those in the config file. Options that are in
+    /// `explicit` are left out.
+    pub fn resolve_default_args(
+        &self,
+        version: &str,
+        explicit: &[OsString],
+    ) -> Result<Vec<String>> {
+        let defaults = self.fs.read_default_args()?;
+
+        let mut scopes = Vec::new();
+
+        if let Some(server_version) = self.server_version(version) {
+            scopes.push(format!("{}.{}", server_version.major, server_version.minor));
+        }
+
+        if let Ok(id) = self.fs.get_newest_matching_version(version) {
+            scopes.push(id);
+        }
+
+        if !scopes.iter().any(|scope| scope == version) {
+            scopes.push(version.into());
+        }
+
+        let mut layers = vec![
+            self.config.mongod.default_args.as_slice(),
+            defaults.global.as_slice(),
+        ];
+        layers.extend(
+            scopes
+                .iter()
+                .filter_map(|scope| defaults.versions.get(scope))
+                .map(Vec::as_slice),
+        );
+
+        Ok(merge_args(&layers, explicit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use super::{merge_args, option_key, split_options};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn options() {
+        assert_eq!(
+            vec![
+                args(&["--port", "27018"]),
+                args(&["--nojournal"]),
+                args(&["--setParameter", "a=b c"]),
+            ],
+            split_options(&args(&[
+                "--port",
+                "27018",
+                "--nojournal",
+                "--setParameter",
+                "a=b c"
+            ]))
+        );
+
+        assert_eq!(Some("--port".into()), option_key(&args(&["--port=27018"])));
+        assert_eq!(
+            Some("--setParameter a".into()),
+            option_key(&args(&["--setParameter", "a=b c"]))
+        );
+        assert_eq!(None, option_key(&args(&["value"])));
+    }
+
+    #[test]
+    fn more_specific_scopes_take_precedence() {
+        let global = args(&[
+            "--quiet",
+            "--wiredTigerCacheSizeGB",
+            "1",
+            "--setParameter",
+            "a=1",
+        ]);
+        let release = args(&["--wiredTigerCacheSizeGB=2", "--setParameter", "b=2"]);
+        let id = args(&["--setParameter", "a=3"]);
+
+        assert_eq!(
+            args(&[
+                "--quiet",
+                "--wiredTigerCacheSizeGB=2",
+                "--setParameter",
+                "a=3",
+                "--setParameter",
+                "b=2",
+            ]),
+            merge_args(&[&global, &release, &id], &[])
+        );
+    }
+
+    #[test]
+    fn explicit_args_take_precedence() {
+        let global = args(&["--quiet", "--port", "27018"]);
+        let explicit: Vec<OsString> = vec!["--port".into(), "27020".into()];
+
+        assert_eq!(args(&["--quiet"]), merge_args(&[&global], &explicit));
+    }
+}
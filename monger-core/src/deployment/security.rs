@@ -0,0 +1,121 @@
+use std::ffi::OsString;
+
+use super::{arg_value, connection_string, Auth};
+use crate::{
+    error::{Error, Result},
+    events::Event,
+    Monger,
+};
+
+const DEFAULT_PORT: u16 = 27017;
+
+/// The security features to enable for a mongod started with `Monger::secure_mongod_args`.
+#[derive(Clone, Debug, Default)]
+pub struct SecurityOptions {
+    /// The credentials of an admin user to create and require clients to authenticate as.
+    pub auth: Option<Auth>,
+
+    /// Whether to require clients to connect over TLS.
+    pub tls: bool,
+}
+
+impl Monger {
+    /// Adds the arguments needed to start a mongod with the given security options, returning
+    /// them along with a connection string for the server. Like the given arguments, they're
+    /// meant to be passed to `Monger::start_mongod` with the same data directory, so they don't
+    /// include the `--dbpath` it adds.
+    ///
+    /// If auth is requested, the admin user is first created by briefly starting the mongod
+    /// without auth on an unused port, so the returned arguments can be used to start it with
+    /// `--auth`.
+    pub fn secure_mongod_args(
+        &self,
+        mut args: Vec<OsString>,
+        version: &str,
+        data: Option<&str>,
+        options: &SecurityOptions,
+    ) -> Result<(Vec<OsString>, String)> {
+        let string_args: Vec<String> = self
+            .process_args(args.clone(), version, data)?
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+
+        let port = arg_value(&string_args, "--port")
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(DEFAULT_PORT);
+
+        if let Some(ref auth) = options.auth {
+            self.bootstrap_root_user(&string_args, version, auth)?;
+            args.push("--auth".into());
+        }
+
+        let tls = if options.tls {
+            let tls = self.tls_files()?;
+            args.extend(
+                tls.server_args(self.uses_legacy_tls_options(version))
+                    .into_iter()
+                    .map(Into::into),
+            );
+
+            Some(tls)
+        } else {
+            None
+        };
+
+        let uri = connection_string(&[port], None, options.auth.as_ref(), tls.as_ref());
+
+        Ok((args, uri))
+    }
+
+    fn bootstrap_root_user(&self, args: &[String], version: &str, auth: &Auth) -> Result<()> {
+        let mut bootstrap_args = Vec::new();
+
+        for name in &["--dbpath", "--storageEngine"] {
+            if let Some(value) = arg_value(args, name) {
+                bootstrap_args.push(name.to_string());
+                bootstrap_args.push(value);
+            }
+        }
+
+        self.events.emit(Event::StartingForUserCreation);
+        self.with_temporary_mongod(version, bootstrap_args, |target| {
+            self.create_root_user(target, auth).or_else(|e| match e {
+                // The user was already created the last time this data directory was
+                // bootstrapped.
+                Error::FailedShellCommand { ref output, .. }
+                    if output.contains("already exists") =>
+                {
+                    Ok(())
+                }
+                e => Err(e),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SecurityOptions;
+    use crate::{config::Config, util::TempDir, Monger};
+
+    #[test]
+    fn tls_with_named_data_dir() {
+        let home_dir = TempDir::new("security");
+        let monger =
+            Monger::with_config(&home_dir, home_dir.join("config.toml"), Config::default())
+                .unwrap();
+
+        let options = SecurityOptions {
+            auth: None,
+            tls: true,
+        };
+        let (args, _) = monger
+            .secure_mongod_args(Vec::new(), "4.4.1", Some("orders"), &options)
+            .unwrap();
+        let args = monger.process_args(args, "4.4.1", Some("orders")).unwrap();
+
+        assert_eq!(1, args.iter().filter(|arg| *arg == "--dbpath").count());
+        assert!(args.iter().any(|arg| *arg == "--tlsMode"));
+    }
+}
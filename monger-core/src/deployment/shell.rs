@@ -0,0 +1,223 @@
+use std::{fs::remove_file, path::PathBuf};
+
+use super::{Auth, TlsFiles};
+use crate::{
+    error::{Error, Result},
+    process::run_command_with_output,
+    util::parse_major_minor_version,
+    Monger,
+};
+
+const SHELL_BINARIES: [&str; 2] = ["mongo", "mongosh"];
+
+/// A local server to run shell commands against.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Target<'a> {
+    pub(crate) version: &'a str,
+    pub(crate) port: u16,
+    pub(crate) auth: Option<&'a Auth>,
+    pub(crate) tls: Option<&'a TlsFiles>,
+}
+
+impl<'a> Target<'a> {
+    pub(crate) fn with_auth(self, auth: Option<&'a Auth>) -> Self {
+        Self { auth, ..self }
+    }
+}
+
+impl Monger {
+    /// Finds a shell that can connect to a server of the given version, preferring the legacy
+    /// `mongo` shell bundled with older releases and falling back to a `mongosh` on the PATH.
+    fn find_shell(&self, version: &str) -> Result<PathBuf> {
+        if !self.fs.is_system(version) {
+            let bin_dir = self.fs.get_version_bin_dir(version)?;
+
+            for binary in &SHELL_BINARIES {
+                let path = bin_dir.join(binary);
+
+                if path.is_file() {
+                    return Ok(path);
+                }
+            }
+        }
+
+        for binary in &SHELL_BINARIES {
+            if let Some(path) = self.find_system_binary(binary) {
+                return Ok(path);
+            }
+        }
+
+        Err(Error::NoShell {
+            version: version.into(),
+        })
+    }
+
+    /// Evaluates a script against the target server and returns its output.
+    pub(crate) fn eval(&self, target: &Target, script: &str) -> Result<String> {
+        let shell = self.find_shell(target.version)?;
+
+        let mut args = vec![
+            "--quiet".to_string(),
+            "--host".into(),
+            super::LOCALHOST.into(),
+            "--port".into(),
+            target.port.to_string(),
+        ];
+
+        if let Some(tls) = target.tls {
+            // mongosh only understands the newer TLS options, regardless of the server version.
+            let legacy = shell.ends_with("mongo") && self.uses_legacy_tls_options(target.version);
+            args.extend(tls.shell_args(legacy));
+        }
+
+        // The script is passed in a file rather than with `--eval`, and authenticates itself
+        // rather than being given the password as an option, so that no password shows up in the
+        // process list. The legacy shell's `auth` returns false on failure, whereas mongosh's
+        // throws.
+        let script = match target.auth {
+            Some(auth) => format!(
+                "if (!db.getSiblingDB(\"admin\").auth({}, {})) {{ \
+                     print('authentication failed'); quit(1); \
+                 }} {}",
+                js_string(&auth.username),
+                js_string(&auth.password),
+                script
+            ),
+            None => script.into(),
+        };
+
+        let script_file = self.fs.write_shell_script(&script)?;
+        args.push(script_file.to_string_lossy().into_owned());
+
+        let output = run_command_with_output(
+            shell.to_string_lossy().as_ref(),
+            args,
+            std::env::current_dir()?,
+        );
+        let _ = remove_file(&script_file);
+        let output = output?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            return Err(Error::FailedShellCommand {
+                port: target.port,
+                output: format!("{}\n{}", stdout, stderr.trim()).trim().to_string(),
+            });
+        }
+
+        Ok(stdout)
+    }
+
+    /// Runs an admin command and fails unless the server reports `ok: 1`.
+    pub(crate) fn run_admin_command(&self, target: &Target, command: &str) -> Result<()> {
+        let script = format!(
+            "var res = db.adminCommand({}); if (!res.ok) {{ printjson(res); quit(1); }}",
+            command
+        );
+
+        self.eval(target, &script).map(|_| ())
+    }
+
+    /// Blocks until the target server reports itself as primary.
+    pub(crate) fn wait_for_primary(&self, target: &Target) -> Result<()> {
+        let script = "var deadline = Date.now() + 60 * 1000; \
+                      while (!db.adminCommand({isMaster: 1}).ismaster) { \
+                          if (Date.now() > deadline) { \
+                              print('timed out waiting for a primary'); quit(1); \
+                          } \
+                          sleep(100); \
+                      }";
+
+        self.eval(target, script).map(|_| ())
+    }
+
+    pub(crate) fn is_primary(&self, target: &Target) -> Result<bool> {
+        let output = self.eval(target, "print(db.adminCommand({isMaster: 1}).ismaster)")?;
+
+        Ok(output.lines().last() == Some("true"))
+    }
+
+    /// The featureCompatibilityVersion of the target server, or `None` if it's too old to have
+    /// one.
+    pub(crate) fn feature_compatibility_version(
+        &self,
+        target: &Target,
+    ) -> Result<Option<(u64, u64)>> {
+        // The parameter is a string in 3.4 and a document in later versions.
+        let script =
+            "var res = db.adminCommand({getParameter: 1, featureCompatibilityVersion: 1}); \
+                      if (res.ok) { \
+                          var fcv = res.featureCompatibilityVersion; \
+                          print(fcv.version || fcv); \
+                      }";
+        let output = self.eval(target, script)?;
+
+        Ok(output.lines().last().and_then(parse_major_minor_version))
+    }
+
+    /// Blocks until the target server reports itself as a healthy primary or secondary.
+    pub(crate) fn wait_for_healthy_member(&self, target: &Target) -> Result<()> {
+        let script = "var deadline = Date.now() + 60 * 1000; \
+                      while (true) { \
+                          var res = db.adminCommand({replSetGetStatus: 1}); \
+                          if (res.ok && (res.myState == 1 || res.myState == 2)) { break; } \
+                          if (Date.now() > deadline) { \
+                              print('timed out waiting for the member to become healthy'); \
+                              quit(1); \
+                          } \
+                          sleep(100); \
+                      }";
+
+        self.eval(target, script).map(|_| ())
+    }
+
+    /// Blocks until the target server sees a primary other than the given host.
+    pub(crate) fn wait_for_new_primary(&self, target: &Target, old_primary: &str) -> Result<()> {
+        let script = format!(
+            "var deadline = Date.now() + 60 * 1000; \
+             while (true) {{ \
+                 var primary = db.adminCommand({{isMaster: 1}}).primary; \
+                 if (primary && primary != {}) {{ break; }} \
+                 if (Date.now() > deadline) {{ \
+                     print('timed out waiting for a new primary'); quit(1); \
+                 }} \
+                 sleep(100); \
+             }}",
+            js_string(old_primary)
+        );
+
+        self.eval(target, &script).map(|_| ())
+    }
+}
+
+/// Quotes a string as a JavaScript string literal.
+pub(crate) fn js_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::js_string;
+
+    #[test]
+    fn quote_js_string() {
+        assert_eq!(r#""admin""#, js_string("admin"));
+        assert_eq!(r#""a\"b\\c""#, js_string(r#"a"b\c"#));
+    }
+}
@@ -0,0 +1,677 @@
+use std::{collections::HashMap, fs::read_to_string, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+const DEFAULT_CLUSTER_NAME: &str = "cluster";
+const DEFAULT_PORT: u16 = 27017;
+const STORAGE_ENGINES: [&str; 4] = ["wiredTiger", "inMemory", "mmapv1", "ephemeralForTest"];
+
+fn default_cluster_name() -> String {
+    DEFAULT_CLUSTER_NAME.into()
+}
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+fn default_one() -> u16 {
+    1
+}
+
+fn default_two() -> u16 {
+    2
+}
+
+fn default_three() -> u16 {
+    3
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The credentials of the initial root user to create in a deployment.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Auth {
+    pub username: String,
+    pub password: String,
+}
+
+/// The shape of a deployment.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Topology {
+    Standalone(StandaloneTopology),
+    ReplicaSet(ReplicaSetTopology),
+    Sharded(ClusterTopology),
+}
+
+/// A single mongod.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StandaloneTopology {
+    pub name: String,
+
+    pub version: String,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    #[serde(default)]
+    pub mongod_args: Vec<String>,
+
+    pub storage_engine: Option<String>,
+
+    pub auth: Option<Auth>,
+
+    #[serde(default)]
+    pub tls: bool,
+}
+
+/// A replica set whose members listen on consecutive ports starting from `port`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplicaSetTopology {
+    pub name: String,
+
+    pub version: String,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    #[serde(default = "default_three")]
+    pub members: u16,
+
+    #[serde(default)]
+    pub mongod_args: Vec<String>,
+
+    pub storage_engine: Option<String>,
+
+    pub auth: Option<Auth>,
+
+    #[serde(default)]
+    pub tls: bool,
+}
+
+/// The shape of a sharded cluster.
+///
+/// Ports are allocated sequentially starting from `port`: first to the mongos routers, then to the
+/// config servers, and finally to the shard members.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterTopology {
+    #[serde(default = "default_cluster_name")]
+    pub name: String,
+
+    pub version: String,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    #[serde(default = "default_one")]
+    pub mongos: u16,
+
+    #[serde(default = "default_one")]
+    pub config_servers: u16,
+
+    #[serde(default)]
+    pub mongod_args: Vec<String>,
+
+    pub storage_engine: Option<String>,
+
+    pub auth: Option<Auth>,
+
+    #[serde(default)]
+    pub tls: bool,
+
+    #[serde(default)]
+    pub shards: ShardTopology,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShardTopology {
+    #[serde(default = "default_two")]
+    pub count: u16,
+
+    #[serde(default = "default_one")]
+    pub members: u16,
+
+    #[serde(default = "default_true")]
+    pub replica_set: bool,
+}
+
+impl Default for ShardTopology {
+    fn default() -> Self {
+        Self {
+            count: default_two(),
+            members: default_one(),
+            replica_set: default_true(),
+        }
+    }
+}
+
+/// A file describing a set of deployments, e.g.:
+///
+/// ```toml
+/// [[deployment]]
+/// name = "orders"
+/// kind = "replica_set"
+/// version = "4.4"
+/// members = 3
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DeploymentFile {
+    #[serde(default, rename = "deployment")]
+    pub deployments: Vec<Topology>,
+}
+
+impl DeploymentFile {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = read_to_string(path)?;
+
+        let file = Self::from_str(&contents).map_err(|e| Error::InvalidTopology {
+            message: format!("{}: {}", path.display(), e),
+        })?;
+
+        file.validate()?;
+
+        Ok(file)
+    }
+
+    fn from_str(contents: &str) -> std::result::Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Validates each deployment as well as ensuring that no two deployments share a name or a
+    /// port.
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashMap::new();
+        let mut ports = HashMap::new();
+
+        for topology in &self.deployments {
+            topology.validate()?;
+
+            if names.insert(topology.name(), ()).is_some() {
+                return Err(Error::InvalidTopology {
+                    message: format!("more than one deployment is named '{}'", topology.name()),
+                });
+            }
+
+            for port in topology.ports() {
+                if let Some(other) = ports.insert(port, topology.name()) {
+                    return Err(Error::InvalidTopology {
+                        message: format!(
+                            "deployments '{}' and '{}' both use port {}",
+                            other,
+                            topology.name(),
+                            port
+                        ),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The ports assigned to each process in a cluster.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PortLayout {
+    pub(crate) mongos: Vec<u16>,
+    pub(crate) config_servers: Vec<u16>,
+    pub(crate) shards: Vec<Vec<u16>>,
+}
+
+impl PortLayout {
+    pub(crate) fn all(&self) -> impl Iterator<Item = u16> + '_ {
+        self.mongos
+            .iter()
+            .chain(self.config_servers.iter())
+            .chain(self.shards.iter().flatten())
+            .cloned()
+    }
+}
+
+fn consecutive_ports(start: u16, count: u16) -> Vec<u16> {
+    (start..).take(count.into()).collect()
+}
+
+impl Topology {
+    pub fn name(&self) -> &str {
+        match self {
+            Topology::Standalone(topology) => &topology.name,
+            Topology::ReplicaSet(topology) => &topology.name,
+            Topology::Sharded(topology) => &topology.name,
+        }
+    }
+
+    pub fn version(&self) -> &str {
+        match self {
+            Topology::Standalone(topology) => &topology.version,
+            Topology::ReplicaSet(topology) => &topology.version,
+            Topology::Sharded(topology) => &topology.version,
+        }
+    }
+
+    pub(crate) fn set_version(&mut self, version: &str) {
+        match self {
+            Topology::Standalone(topology) => topology.version = version.into(),
+            Topology::ReplicaSet(topology) => topology.version = version.into(),
+            Topology::Sharded(topology) => topology.version = version.into(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Topology::Standalone(_) => "standalone",
+            Topology::ReplicaSet(_) => "replica set",
+            Topology::Sharded(_) => "sharded cluster",
+        }
+    }
+
+    pub fn auth(&self) -> Option<&Auth> {
+        match self {
+            Topology::Standalone(topology) => topology.auth.as_ref(),
+            Topology::ReplicaSet(topology) => topology.auth.as_ref(),
+            Topology::Sharded(topology) => topology.auth.as_ref(),
+        }
+    }
+
+    pub fn tls(&self) -> bool {
+        match self {
+            Topology::Standalone(topology) => topology.tls,
+            Topology::ReplicaSet(topology) => topology.tls,
+            Topology::Sharded(topology) => topology.tls,
+        }
+    }
+
+    fn storage_engine(&self) -> Option<&str> {
+        match self {
+            Topology::Standalone(topology) => topology.storage_engine.as_deref(),
+            Topology::ReplicaSet(topology) => topology.storage_engine.as_deref(),
+            Topology::Sharded(topology) => topology.storage_engine.as_deref(),
+        }
+    }
+
+    fn user_mongod_args(&self) -> &[String] {
+        match self {
+            Topology::Standalone(topology) => &topology.mongod_args,
+            Topology::ReplicaSet(topology) => &topology.mongod_args,
+            Topology::Sharded(topology) => &topology.mongod_args,
+        }
+    }
+
+    /// The arguments from the topology that should be passed to every mongod in the deployment.
+    pub(crate) fn mongod_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(storage_engine) = self.storage_engine() {
+            args.push("--storageEngine".to_string());
+            args.push(storage_engine.to_string());
+        }
+
+        args.extend(self.user_mongod_args().iter().cloned());
+        args
+    }
+
+    pub fn ports(&self) -> Vec<u16> {
+        match self {
+            Topology::Standalone(topology) => vec![topology.port],
+            Topology::ReplicaSet(topology) => consecutive_ports(topology.port, topology.members),
+            Topology::Sharded(topology) => topology.port_layout().all().collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let result = self.validate_inner();
+
+        result.map_err(|message| Error::InvalidTopology {
+            message: format!("deployment '{}': {}", self.name(), message),
+        })
+    }
+
+    fn validate_inner(&self) -> std::result::Result<(), String> {
+        let name = self.name();
+
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(
+                "the name must be non-empty and contain only letters, digits, '-' and '_'".into(),
+            );
+        }
+
+        if self.version().is_empty() {
+            return Err("a version must be specified".into());
+        }
+
+        if let Some(storage_engine) = self.storage_engine() {
+            if !STORAGE_ENGINES.contains(&storage_engine) {
+                return Err(format!(
+                    "unknown storage engine '{}'; expected one of {}",
+                    storage_engine,
+                    STORAGE_ENGINES.join(", ")
+                ));
+            }
+        }
+
+        if let Some(auth) = self.auth() {
+            if auth.username.is_empty() || auth.password.is_empty() {
+                return Err("auth requires a non-empty username and password".into());
+            }
+        }
+
+        let (port, process_count) = match self {
+            Topology::Standalone(topology) => (topology.port, 1),
+            Topology::ReplicaSet(topology) => {
+                if topology.members == 0 || topology.members > 50 {
+                    return Err(format!(
+                        "a replica set must have between 1 and 50 members, not {}",
+                        topology.members
+                    ));
+                }
+
+                (topology.port, u32::from(topology.members))
+            }
+            Topology::Sharded(topology) => {
+                topology.validate_shape()?;
+
+                (topology.port, topology.process_count())
+            }
+        };
+
+        if port == 0 {
+            return Err("the port must be non-zero".into());
+        }
+
+        if u32::from(port) + process_count - 1 > u32::from(u16::MAX) {
+            return Err(format!(
+                "{} processes starting at port {} would exceed the maximum port number",
+                process_count, port
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl ClusterTopology {
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            port: default_port(),
+            mongos: default_one(),
+            config_servers: default_one(),
+            mongod_args: Vec::new(),
+            storage_engine: None,
+            auth: None,
+            tls: false,
+            shards: Default::default(),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+
+        let topology: Self =
+            toml::from_str(&read_to_string(path)?).map_err(|e| Error::InvalidTopology {
+                message: format!("{}: {}", path.display(), e),
+            })?;
+
+        Topology::Sharded(topology.clone()).validate()?;
+
+        Ok(topology)
+    }
+
+    fn process_count(&self) -> u32 {
+        u32::from(self.mongos)
+            + u32::from(self.config_servers)
+            + u32::from(self.shards.count) * u32::from(self.shards.members)
+    }
+
+    fn validate_shape(&self) -> std::result::Result<(), String> {
+        if self.mongos == 0 {
+            return Err("at least one mongos is required".into());
+        }
+
+        if self.config_servers == 0 {
+            return Err("at least one config server is required".into());
+        }
+
+        if self.shards.count == 0 {
+            return Err("at least one shard is required".into());
+        }
+
+        if self.shards.members == 0 {
+            return Err("each shard must have at least one member".into());
+        }
+
+        if !self.shards.replica_set && self.shards.members != 1 {
+            return Err(format!(
+                "shards that are not replica sets must have exactly one member, not {}",
+                self.shards.members
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn port_layout(&self) -> PortLayout {
+        let mut ports = self.port..;
+        let mut take = |n: u16| -> Vec<u16> { ports.by_ref().take(n.into()).collect() };
+
+        let mongos = take(self.mongos);
+        let config_servers = take(self.config_servers);
+        let shards = (0..self.shards.count)
+            .map(|_| take(self.shards.members))
+            .collect();
+
+        PortLayout {
+            mongos,
+            config_servers,
+            shards,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClusterTopology, DeploymentFile, PortLayout, Topology};
+
+    fn sharded(topology: ClusterTopology) -> Topology {
+        Topology::Sharded(topology)
+    }
+
+    #[test]
+    fn parse_cluster_defaults() {
+        let topology: ClusterTopology = toml::from_str(r#"version = "4.4""#).unwrap();
+
+        assert_eq!(ClusterTopology::new("cluster", "4.4"), topology);
+        assert!(sharded(topology).validate().is_ok());
+    }
+
+    #[test]
+    fn parse_cluster_full() {
+        let topology: ClusterTopology = toml::from_str(
+            r#"
+            name = "shard-key-test"
+            version = "5.0.14"
+            port = 30000
+            mongos = 2
+            config_servers = 3
+            mongod_args = ["--setParameter", "enableTestCommands=1"]
+            tls = true
+
+            [shards]
+            count = 3
+            members = 3
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!("shard-key-test", topology.name);
+        assert_eq!(30000, topology.port);
+        assert_eq!(3, topology.shards.count);
+        assert_eq!(3, topology.shards.members);
+        assert!(topology.shards.replica_set);
+        assert!(topology.tls);
+        assert!(sharded(topology).validate().is_ok());
+    }
+
+    #[test]
+    fn reject_unknown_field() {
+        assert!(toml::from_str::<ClusterTopology>("version = \"4.4\"\nshard = 2").is_err());
+    }
+
+    #[test]
+    fn reject_standalone_shard_with_members() {
+        let mut topology = ClusterTopology::new("cluster", "4.4");
+        topology.shards.replica_set = false;
+        topology.shards.members = 3;
+
+        assert!(sharded(topology).validate().is_err());
+    }
+
+    #[test]
+    fn reject_port_overflow() {
+        let mut topology = ClusterTopology::new("cluster", "4.4");
+        topology.port = 65534;
+
+        assert!(sharded(topology).validate().is_err());
+    }
+
+    #[test]
+    fn port_layout() {
+        let mut topology = ClusterTopology::new("cluster", "4.4");
+        topology.shards.members = 2;
+
+        assert_eq!(
+            PortLayout {
+                mongos: vec![27017],
+                config_servers: vec![27018],
+                shards: vec![vec![27019, 27020], vec![27021, 27022]],
+            },
+            topology.port_layout()
+        );
+    }
+
+    #[test]
+    fn parse_deployment_file() {
+        let file = DeploymentFile::from_str(
+            r#"
+            [[deployment]]
+            name = "single"
+            kind = "standalone"
+            version = "4.4"
+            storage_engine = "inMemory"
+
+            [[deployment]]
+            name = "orders"
+            kind = "replica_set"
+            version = "5.0"
+            port = 28017
+            auth = { username = "admin", password = "hunter2" }
+
+            [[deployment]]
+            name = "sharded"
+            kind = "sharded"
+            version = "6.0"
+            port = 29017
+            shards = { count = 3 }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(3, file.deployments.len());
+        assert_eq!(vec![28017, 28018, 28019], file.deployments[1].ports());
+        assert_eq!(
+            vec!["--storageEngine", "inMemory"],
+            file.deployments[0].mongod_args()
+        );
+        assert!(file.validate().is_ok());
+    }
+
+    #[test]
+    fn reject_unknown_kind() {
+        let result = DeploymentFile::from_str(
+            r#"
+            [[deployment]]
+            name = "single"
+            kind = "cluster"
+            version = "4.4"
+            "#,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn reject_overlapping_ports() {
+        let file = DeploymentFile::from_str(
+            r#"
+            [[deployment]]
+            name = "single"
+            kind = "standalone"
+            version = "4.4"
+            port = 27019
+
+            [[deployment]]
+            name = "orders"
+            kind = "replica_set"
+            version = "5.0"
+            "#,
+        )
+        .unwrap();
+
+        let message = file.validate().unwrap_err().to_string();
+
+        assert!(message.contains("27019"), "{}", message);
+    }
+
+    #[test]
+    fn reject_duplicate_names() {
+        let file = DeploymentFile::from_str(
+            r#"
+            [[deployment]]
+            name = "single"
+            kind = "standalone"
+            version = "4.4"
+
+            [[deployment]]
+            name = "single"
+            kind = "standalone"
+            version = "4.4"
+            port = 28017
+            "#,
+        )
+        .unwrap();
+
+        assert!(file.validate().is_err());
+    }
+
+    #[test]
+    fn reject_unknown_storage_engine() {
+        let file = DeploymentFile::from_str(
+            r#"
+            [[deployment]]
+            name = "single"
+            kind = "standalone"
+            version = "4.4"
+            storage_engine = "rocksdb"
+            "#,
+        )
+        .unwrap();
+
+        assert!(file.validate().is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use super::{DeploymentState, Target, Topology, LOCALHOST, STOP_TIMEOUT};
+use crate::{
+    error::{Error, Result},
+    events::Event,
+    fcv::{check_upgrade, set_fcv_command},
+    process::stop_process,
+    Monger,
+};
+
+fn member_target<'a>(state: &'a DeploymentState, version: &'a str, index: usize) -> Target<'a> {
+    Target {
+        version,
+        port: state.members[index].port,
+        auth: state.topology.auth(),
+        tls: state.tls.as_ref(),
+    }
+}
+
+impl Monger {
+    /// Upgrades a running replica set started by monger to another version the way it would be
+    /// done in production: each secondary is restarted on the new version in turn, then the
+    /// primary is stepped down and restarted as well. If `set_fcv` is true, the
+    /// featureCompatibilityVersion is then set to the new version.
+    ///
+    /// The deployment waits for each restarted member to become healthy before moving on, and
+    /// stops at the first step that fails.
+    pub fn rolling_upgrade(&self, name: &str, to: &str, set_fcv: bool) -> Result<DeploymentState> {
+        let mut state = self.get_deployment(name)?;
+
+        if !matches!(state.topology, Topology::ReplicaSet(..)) {
+            return Err(Error::NotReplicaSet { name: name.into() });
+        }
+
+        if !state.is_running() {
+            return Err(Error::DeploymentNotRunning { name: name.into() });
+        }
+
+        let to_version = self
+            .server_version(to)
+            .ok_or_else(|| Error::VersionNotFound { version: to.into() })?;
+
+        let primary = self.find_primary(&state, &state.version)?;
+
+        // The featureCompatibilityVersion may lag behind the binaries if it wasn't set after the
+        // last upgrade, and it's what decides which versions the members can be restarted on.
+        let fcv =
+            self.feature_compatibility_version(&member_target(&state, &state.version, primary))?;
+        let from_version = self.server_version(&state.version);
+
+        if let Some(reason) = check_upgrade(from_version.as_ref(), fcv, &to_version) {
+            return Err(Error::InvalidUpgrade {
+                name: name.into(),
+                version: to.into(),
+                reason,
+            });
+        }
+
+        let secondaries: Vec<_> = (0..state.members.len()).filter(|i| *i != primary).collect();
+
+        // Each secondary is restarted, then the primary is stepped down (if there's another
+        // member to take over) and restarted.
+        let steps =
+            secondaries.len() + usize::from(!secondaries.is_empty()) + 1 + usize::from(set_fcv);
+        let mut step = 0;
+
+        for i in secondaries.iter().copied() {
+            step += 1;
+            self.events.emit(Event::UpgradeStep {
+                step,
+                steps,
+                description: format!("restarting secondary {} on {}", state.members[i].name, to),
+            });
+
+            self.restart_member(&mut state, i, to)?;
+            self.wait_for_healthy_member(&member_target(&state, to, i))?;
+        }
+
+        if let Some(secondary) = secondaries.first() {
+            step += 1;
+            self.events.emit(Event::UpgradeStep {
+                step,
+                steps,
+                description: format!("stepping down primary {}", state.members[primary].name),
+            });
+
+            // The primary closes all connections when it steps down, so the shell may report an
+            // error even when the step down succeeds.
+            let _ = self.run_admin_command(
+                &member_target(&state, &state.version, primary),
+                "{replSetStepDown: 60}",
+            );
+
+            let old_host = format!("{}:{}", LOCALHOST, state.members[primary].port);
+            self.wait_for_new_primary(&member_target(&state, to, *secondary), &old_host)?;
+        }
+
+        step += 1;
+        self.events.emit(Event::UpgradeStep {
+            step,
+            steps,
+            description: format!(
+                "restarting former primary {} on {}",
+                state.members[primary].name, to
+            ),
+        });
+
+        self.restart_member(&mut state, primary, to)?;
+        self.wait_for_healthy_member(&member_target(&state, to, primary))?;
+
+        state.version = to.into();
+        state.topology.set_version(to);
+        self.write_deployment_state(&state)?;
+
+        if set_fcv {
+            step += 1;
+            let release = (to_version.major, to_version.minor);
+
+            self.events.emit(Event::UpgradeStep {
+                step,
+                steps,
+                description: format!(
+                    "setting featureCompatibilityVersion to {}.{}",
+                    release.0, release.1
+                ),
+            });
+
+            if secondaries.is_empty() {
+                self.wait_for_primary(&member_target(&state, to, primary))?;
+            }
+
+            let new_primary = self.find_primary(&state, to)?;
+            self.run_admin_command(
+                &member_target(&state, to, new_primary),
+                &set_fcv_command(release),
+            )?;
+        }
+
+        Ok(state)
+    }
+
+    fn find_primary(&self, state: &DeploymentState, version: &str) -> Result<usize> {
+        for i in 0..state.members.len() {
+            if self.is_primary(&member_target(state, version, i))? {
+                return Ok(i);
+            }
+        }
+
+        Err(Error::NoPrimary {
+            name: state.name.clone(),
+        })
+    }
+
+    /// Restarts a member of a deployment on another version and records its new PID.
+    fn restart_member(
+        &self,
+        state: &mut DeploymentState,
+        index: usize,
+        version: &str,
+    ) -> Result<()> {
+        stop_process(state.members[index].pid, STOP_TIMEOUT)?;
+
+        state.members[index] = self.launch_member(state.members[index].clone(), version)?;
+        self.write_deployment_state(state)
+    }
+}
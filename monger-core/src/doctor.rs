@@ -0,0 +1,744 @@
+use std::{
+    collections::BTreeSet,
+    env,
+    fmt::{self, Display},
+    fs::File,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use rs_release::get_os_release;
+use serde::Serialize;
+
+use crate::{
+    deployment::{arg_value, check_port_available},
+    error::Result,
+    os::{LinuxType, OperatingSystem},
+    process::run_command_with_output,
+    Monger,
+};
+
+const DEFAULT_PORT: u16 = 27017;
+
+/// The directories the dynamic linker searches by default on the distributions MongoDB builds for.
+const LIBRARY_DIRS: &[&str] = &[
+    "/lib",
+    "/lib64",
+    "/usr/lib",
+    "/usr/lib64",
+    "/lib/x86_64-linux-gnu",
+    "/usr/lib/x86_64-linux-gnu",
+    "/lib/aarch64-linux-gnu",
+    "/usr/lib/aarch64-linux-gnu",
+];
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const DT_VERNEED: u64 = 0x6fff_fffe;
+const DYNAMIC_ENTRY_SIZE: u64 = 16;
+
+/// The longest library name or symbol version that is read from an executable.
+const MAX_STRING_LEN: u64 = 4096;
+
+/// How serious a problem found by `Monger::diagnose` is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Ok,
+
+    /// Something that may cause problems, but doesn't stop monger from starting a mongod.
+    Warning,
+
+    /// Something that stops monger from installing or starting a mongod.
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Ok => write!(f, "ok"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The result of one of the checks run by `Monger::diagnose`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Diagnosis {
+    /// What was checked, e.g. "libraries".
+    pub check: String,
+
+    pub severity: Severity,
+    pub message: String,
+
+    /// How to fix the problem, if there is one.
+    pub hint: Option<String>,
+}
+
+impl Diagnosis {
+    fn new(check: &str, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            check: check.into(),
+            severity,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Whether the problem stops monger from installing or starting a mongod.
+    pub fn is_blocking(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// The dynamic linker's view of an ELF executable.
+#[derive(Debug, Default, PartialEq)]
+struct DynamicInfo {
+    /// The shared libraries it needs (i.e. its `DT_NEEDED` entries).
+    libraries: Vec<String>,
+
+    /// The symbol versions it needs from those libraries (e.g. "GLIBC_2.17").
+    symbol_versions: Vec<String>,
+}
+
+/// An ELF program header, i.e. where a segment is in the file and where it's loaded in memory.
+struct ProgramHeader {
+    kind: u32,
+    offset: u64,
+    address: u64,
+    size: u64,
+}
+
+impl ProgramHeader {
+    /// Maps a virtual address in the segment back to an offset in the file.
+    fn file_offset(&self, address: u64) -> Option<u64> {
+        if self.kind != PT_LOAD || address < self.address || address - self.address >= self.size {
+            return None;
+        }
+
+        self.offset.checked_add(address - self.address)
+    }
+}
+
+/// Reads fields from a 64-bit little-endian ELF file by seeking to them, so that only the headers
+/// and the dynamic section are read rather than the whole executable.
+struct ElfReader<R> {
+    file: R,
+}
+
+impl<R: BufRead + Seek> ElfReader<R> {
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Option<()> {
+        self.file.seek(SeekFrom::Start(offset)).ok()?;
+        self.file.read_exact(buf).ok()
+    }
+
+    fn u16_at(&mut self, offset: u64) -> Option<u16> {
+        let mut bytes = [0; 2];
+        self.read_at(offset, &mut bytes)?;
+
+        Some(u16::from_le_bytes(bytes))
+    }
+
+    fn u32_at(&mut self, offset: u64) -> Option<u32> {
+        let mut bytes = [0; 4];
+        self.read_at(offset, &mut bytes)?;
+
+        Some(u32::from_le_bytes(bytes))
+    }
+
+    fn u64_at(&mut self, offset: u64) -> Option<u64> {
+        let mut bytes = [0; 8];
+        self.read_at(offset, &mut bytes)?;
+
+        Some(u64::from_le_bytes(bytes))
+    }
+
+    /// Reads a null-terminated string, giving up on ones that are implausibly long.
+    fn string_at(&mut self, offset: u64) -> Option<String> {
+        self.file.seek(SeekFrom::Start(offset)).ok()?;
+
+        let mut bytes = Vec::new();
+        self.file
+            .by_ref()
+            .take(MAX_STRING_LEN)
+            .read_until(0, &mut bytes)
+            .ok()?;
+
+        if bytes.pop()? != 0 {
+            return None;
+        }
+
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+/// Reads the shared libraries and symbol versions that a 64-bit little-endian ELF executable
+/// needs, or `None` if it isn't one.
+fn read_dynamic_info(file: impl BufRead + Seek) -> Option<DynamicInfo> {
+    let mut elf = ElfReader { file };
+
+    let mut magic = [0; 6];
+    elf.read_at(0, &mut magic)?;
+
+    if &magic != b"\x7fELF\x02\x01" {
+        return None;
+    }
+
+    let header_offset = elf.u64_at(0x20)?;
+    let header_size = u64::from(elf.u16_at(0x36)?);
+    let header_count = u64::from(elf.u16_at(0x38)?);
+
+    let headers = (0..header_count)
+        .map(|i| {
+            let header = header_offset.checked_add(i * header_size)?;
+
+            Some(ProgramHeader {
+                kind: elf.u32_at(header)?,
+                offset: elf.u64_at(header.checked_add(8)?)?,
+                address: elf.u64_at(header.checked_add(16)?)?,
+                size: elf.u64_at(header.checked_add(32)?)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let file_offset = |address| headers.iter().find_map(|h| h.file_offset(address));
+
+    // Statically linked executables don't need any libraries.
+    let dynamic = match headers.iter().find(|h| h.kind == PT_DYNAMIC) {
+        Some(header) => header,
+        None => return Some(DynamicInfo::default()),
+    };
+
+    let mut needed = Vec::new();
+    let mut string_table = None;
+    let mut version_needs = None;
+
+    for i in 0..dynamic.size / DYNAMIC_ENTRY_SIZE {
+        let entry = dynamic.offset.checked_add(i * DYNAMIC_ENTRY_SIZE)?;
+        let value = elf.u64_at(entry.checked_add(8)?)?;
+
+        match elf.u64_at(entry)? {
+            DT_NULL => break,
+            DT_NEEDED => needed.push(value),
+            DT_STRTAB => string_table = Some(value),
+            DT_VERNEED => version_needs = Some(value),
+            _ => {}
+        }
+    }
+
+    // The dynamic section gives addresses in memory, which have to be mapped back to offsets in
+    // the file through the segment they're loaded in.
+    let string_table = file_offset(string_table?)?;
+
+    let libraries = needed
+        .into_iter()
+        .map(|index| elf.string_at(string_table.checked_add(index)?))
+        .collect::<Option<Vec<_>>>()?;
+
+    // Each library that symbols are needed from has an entry in a linked list, which has a linked
+    // list of the symbol versions needed from it. The offset of the next item is 0 for the last.
+    let mut symbol_versions = Vec::new();
+    let mut entry = match version_needs {
+        Some(address) => Some(file_offset(address)?),
+        None => None,
+    };
+
+    while let Some(offset) = entry {
+        let mut aux = offset.checked_add(u64::from(elf.u32_at(offset.checked_add(8)?)?))?;
+
+        for _ in 0..elf.u16_at(offset.checked_add(2)?)? {
+            let name = elf.u32_at(aux.checked_add(8)?)?;
+            symbol_versions.push(elf.string_at(string_table.checked_add(u64::from(name))?)?);
+
+            aux = aux.checked_add(u64::from(elf.u32_at(aux.checked_add(12)?)?))?;
+        }
+
+        entry = match elf.u32_at(offset.checked_add(12)?)? {
+            0 => None,
+            next => Some(offset.checked_add(u64::from(next))?),
+        };
+    }
+
+    Some(DynamicInfo {
+        libraries,
+        symbol_versions,
+    })
+}
+
+/// Parses a glibc version like "2.17" into its major and minor numbers.
+fn parse_glibc_version(version: &str) -> Option<(u32, u32)> {
+    let mut numbers = version.split('.');
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers.next()?.parse().ok()?;
+
+    Some((major, minor))
+}
+
+/// The newest glibc version that an executable requires a symbol from, found from the
+/// `GLIBC_<VERSION>` symbol versions it needs.
+fn required_glibc_version(symbol_versions: &[String]) -> Option<(u32, u32)> {
+    symbol_versions
+        .iter()
+        .filter_map(|version| parse_glibc_version(version.strip_prefix("GLIBC_")?))
+        .max()
+}
+
+/// The version of the system's glibc, as reported by `ldd --version`.
+fn installed_glibc_version() -> Option<(u32, u32)> {
+    let output = run_command_with_output("ldd", vec!["--version"], env::temp_dir()).ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // The first line is e.g. "ldd (Ubuntu GLIBC 2.31-0ubuntu9.9) 2.31".
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()?
+        .split_whitespace()
+        .last()
+        .and_then(parse_glibc_version)
+}
+
+/// The shared libraries known to the dynamic linker's cache, as listed by `ldconfig -p`.
+fn cached_libraries() -> BTreeSet<String> {
+    let output = ["ldconfig", "/sbin/ldconfig"]
+        .iter()
+        .find_map(|ldconfig| run_command_with_output(ldconfig, vec!["-p"], env::temp_dir()).ok());
+
+    output
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| line.contains(" => "))
+                .filter_map(|line| line.split_whitespace().next())
+                .map(Into::into)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether the dynamic linker can find a shared library.
+fn library_exists(library: &str, cached: &BTreeSet<String>) -> bool {
+    if cached.contains(library) {
+        return true;
+    }
+
+    let library_path = env::var_os("LD_LIBRARY_PATH").unwrap_or_default();
+
+    env::split_paths(&library_path)
+        .chain(LIBRARY_DIRS.iter().map(Into::into))
+        .any(|dir| dir.join(library).exists())
+}
+
+/// Suggests how to install a missing shared library.
+fn library_hint(library: &str) -> String {
+    let package = match library.split(".so").next().unwrap_or(library) {
+        "libcurl" => "libcurl (e.g. `apt install libcurl4` or `yum install libcurl`)",
+        "libssl" | "libcrypto" if library.ends_with(".so.1.1") => {
+            "OpenSSL 1.1, or use a build for a newer distribution with `--os`"
+        }
+        "libssl" | "libcrypto" if library.ends_with(".so.10") => {
+            "OpenSSL 1.0 (`yum install openssl-libs`), or use a build for a newer distribution \
+             with `--os`"
+        }
+        "libssl" | "libcrypto" => {
+            "the OpenSSL version the build was linked against, or use a build for your \
+             distribution with `--os`"
+        }
+        "liblzma" => "xz (e.g. `apt install liblzma5` or `yum install xz-libs`)",
+        "libsasl2" | "libldap" | "liblber" | "libgssapi_krb5" | "libkrb5" | "libnetsnmpagent"
+        | "libnetsnmpmibs" | "libnetsnmp" => {
+            "the libraries MongoDB Enterprise depends on (see its installation instructions)"
+        }
+        _ => return format!("install the package that provides {}", library),
+    };
+
+    format!("install {}", package)
+}
+
+/// The name of the distribution as given by /etc/os-release, if it can be read.
+fn distribution_name() -> Option<String> {
+    let info = get_os_release().ok()?;
+
+    info.get("PRETTY_NAME")
+        .or_else(|| info.get("NAME"))
+        .cloned()
+}
+
+impl Monger {
+    /// Checks whether this machine can run a version installed by monger: whether the platform is
+    /// one monger knows builds for, whether monger's directories are writable, whether the shared
+    /// libraries and glibc that the version's mongod needs are installed, and whether the port it
+    /// would listen on is free. Without an ID, the version that would be used by default is
+    /// checked, if there is one.
+    pub fn diagnose(&self, id: Option<&str>) -> Result<Vec<Diagnosis>> {
+        let mut diagnoses = vec![self.diagnose_platform()];
+        diagnoses.extend(self.diagnose_dirs());
+
+        let version = match (id, self.resolve_version(None)) {
+            (Some(id), _) => id.to_string(),
+            (None, Ok(version)) => version,
+            (None, Err(_)) => {
+                diagnoses.push(
+                    Diagnosis::new(
+                        "version",
+                        Severity::Warning,
+                        "no version was given, and none is pinned or set as the default, so no \
+                         mongod was checked",
+                    )
+                    .with_hint("pass the ID of an installed version to `monger doctor`"),
+                );
+
+                return Ok(diagnoses);
+            }
+        };
+
+        let mongod = match self.binary_path(&version, "mongod") {
+            Ok(mongod) => mongod,
+            Err(_) if self.fs.is_system(&version) => {
+                diagnoses.push(
+                    Diagnosis::new(
+                        "version",
+                        Severity::Error,
+                        "no mongod was found on the PATH",
+                    )
+                    .with_hint("install MongoDB, or check a version installed by monger instead"),
+                );
+
+                return Ok(diagnoses);
+            }
+            Err(_) => {
+                diagnoses.push(
+                    Diagnosis::new(
+                        "version",
+                        Severity::Error,
+                        format!("{} has no mongod installed", version),
+                    )
+                    .with_hint(format!("install it with `monger get {}`", version)),
+                );
+
+                return Ok(diagnoses);
+            }
+        };
+
+        diagnoses.push(Diagnosis::new(
+            "version",
+            Severity::Ok,
+            format!("checking {} ({})", version, mongod.display()),
+        ));
+        diagnoses.extend(self.diagnose_build(&version));
+        diagnoses.extend(diagnose_binary(&mongod)?);
+        diagnoses.push(self.diagnose_port(&version)?);
+
+        Ok(diagnoses)
+    }
+
+    /// Checks which builds monger downloads for this machine.
+    fn diagnose_platform(&self) -> Diagnosis {
+        const CHECK: &str = "platform";
+
+        if let Some(ref os) = self.config.download.os {
+            return Diagnosis::new(
+                CHECK,
+                Severity::Ok,
+                format!("downloading builds for {} (set by download.os)", os),
+            );
+        }
+
+        if env::consts::OS != "linux" {
+            return match OperatingSystem::get(&semver::Version::new(4, 4, 0)) {
+                Ok(os) => Diagnosis::new(
+                    CHECK,
+                    Severity::Ok,
+                    format!("downloading builds for {}", os.target()),
+                ),
+                Err(e) => Diagnosis::new(CHECK, Severity::Error, e.to_string()),
+            };
+        }
+
+        let hint = "pick the build for a compatible distribution with `--os` or `monger config \
+                    set download.os <OS>`";
+        let distribution = distribution_name().unwrap_or_else(|| "this distribution".into());
+
+        match LinuxType::get() {
+            Ok(LinuxType::Legacy) => Diagnosis::new(
+                CHECK,
+                Severity::Warning,
+                format!(
+                    "{} isn't a distribution monger knows builds for, so the generic Linux \
+                     builds are downloaded, which don't exist for every version",
+                    distribution
+                ),
+            )
+            .with_hint(hint),
+            Ok(os_type) => Diagnosis::new(
+                CHECK,
+                Severity::Ok,
+                format!(
+                    "downloading builds for {} on {}",
+                    OperatingSystem::Linux(os_type).target(),
+                    distribution
+                ),
+            ),
+            Err(e) => Diagnosis::new(CHECK, Severity::Error, e.to_string()).with_hint(hint),
+        }
+    }
+
+    /// Checks that monger can write to the directories it stores versions and data in.
+    fn diagnose_dirs(&self) -> Vec<Diagnosis> {
+        const CHECK: &str = "permissions";
+
+        let problems: Vec<_> = self
+            .fs
+            .unwritable_dirs()
+            .into_iter()
+            .map(|(dir, e)| {
+                Diagnosis::new(
+                    CHECK,
+                    Severity::Error,
+                    format!("can't write to {}: {}", dir.display(), e),
+                )
+                .with_hint(format!(
+                    "make sure {} is owned by you (e.g. `sudo chown -R $USER {}`)",
+                    dir.display(),
+                    dir.display()
+                ))
+            })
+            .collect();
+
+        if problems.is_empty() {
+            vec![Diagnosis::new(
+                CHECK,
+                Severity::Ok,
+                "monger's directories are writable",
+            )]
+        } else {
+            problems
+        }
+    }
+
+    /// Checks that an installed version was built for this machine, according to its manifest.
+    fn diagnose_build(&self, version: &str) -> Option<Diagnosis> {
+        let manifest = self.fs.read_manifest(version).ok()??;
+        let built_for = manifest.os?;
+
+        // The version only affects which builds are downloaded for releases before 3.0 on macOS.
+        let expected = match self.config.download.os {
+            Some(ref os) => os.clone(),
+            None => OperatingSystem::get(&semver::Version::new(4, 4, 0))
+                .ok()?
+                .target()
+                .to_string(),
+        };
+
+        if built_for == expected {
+            return None;
+        }
+
+        Some(
+            Diagnosis::new(
+                "platform",
+                Severity::Warning,
+                format!(
+                    "{} was built for {}, but monger downloads builds for {} on this machine",
+                    version, built_for, expected
+                ),
+            )
+            .with_hint(format!(
+                "reinstall it with `monger get {} --force`",
+                version
+            )),
+        )
+    }
+
+    /// Checks that the port a mongod of the version would listen on by default is free.
+    fn diagnose_port(&self, version: &str) -> Result<Diagnosis> {
+        const CHECK: &str = "port";
+
+        let args = self.resolve_default_args(version, &[])?;
+        let port = arg_value(&args, "--port")
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(DEFAULT_PORT);
+
+        if check_port_available(port).is_ok() {
+            return Ok(Diagnosis::new(
+                CHECK,
+                Severity::Ok,
+                format!("port {} is free", port),
+            ));
+        }
+
+        let deployment = self.list_deployments()?.into_iter().find(|deployment| {
+            deployment
+                .members
+                .iter()
+                .any(|member| member.port == port && member.is_running())
+        });
+
+        let message = match deployment {
+            Some(deployment) => format!(
+                "port {} is already in use by deployment {}",
+                port, deployment.name
+            ),
+            None => format!("port {} is already in use", port),
+        };
+
+        Ok(Diagnosis::new(CHECK, Severity::Warning, message)
+            .with_hint("stop whatever is listening on it, or start mongod with `--port <PORT>`"))
+    }
+}
+
+/// Checks that the shared libraries and glibc version a mongod needs are installed.
+fn diagnose_binary(mongod: &Path) -> Result<Vec<Diagnosis>> {
+    let info = match read_dynamic_info(BufReader::new(File::open(mongod)?)) {
+        Some(info) => info,
+        None => {
+            return Ok(vec![Diagnosis::new(
+                "libraries",
+                Severity::Warning,
+                format!(
+                    "{} isn't a 64-bit ELF executable, so its libraries weren't checked",
+                    mongod.display()
+                ),
+            )])
+        }
+    };
+
+    let cached = cached_libraries();
+    let mut diagnoses: Vec<_> = info
+        .libraries
+        .iter()
+        .filter(|library| !library_exists(library, &cached))
+        .map(|library| {
+            Diagnosis::new(
+                "libraries",
+                Severity::Error,
+                format!("mongod needs {}, which isn't installed", library),
+            )
+            .with_hint(library_hint(library))
+        })
+        .collect();
+
+    if diagnoses.is_empty() {
+        diagnoses.push(Diagnosis::new(
+            "libraries",
+            Severity::Ok,
+            format!(
+                "all {} libraries mongod needs are installed",
+                info.libraries.len()
+            ),
+        ));
+    }
+
+    let required = required_glibc_version(&info.symbol_versions);
+    let installed = installed_glibc_version();
+
+    diagnoses.push(match (required, installed) {
+        (None, _) => Diagnosis::new("glibc", Severity::Ok, "mongod doesn't need glibc"),
+        (Some(_), None) => Diagnosis::new(
+            "glibc",
+            Severity::Error,
+            "mongod needs glibc, but it couldn't be found",
+        )
+        .with_hint("MongoDB's Linux builds need a glibc-based distribution"),
+        (Some(required), Some(installed)) if required > installed => Diagnosis::new(
+            "glibc",
+            Severity::Error,
+            format!(
+                "mongod needs glibc {}.{}, but {}.{} is installed",
+                required.0, required.1, installed.0, installed.1
+            ),
+        )
+        .with_hint("use a build for an older distribution with `--os`"),
+        (Some(required), Some(installed)) => Diagnosis::new(
+            "glibc",
+            Severity::Ok,
+            format!(
+                "mongod needs glibc {}.{}, and {}.{} is installed",
+                required.0, required.1, installed.0, installed.1
+            ),
+        ),
+    });
+
+    Ok(diagnoses)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{
+        library_hint, parse_glibc_version, read_dynamic_info, required_glibc_version, DynamicInfo,
+    };
+
+    /// A minimal executable that needs libc.so.6 and libcurl.so.4, and the GLIBC_2.2.5,
+    /// GLIBC_2.28, GLIBC_2.3 and GLIBC_PRIVATE symbol versions from libc.
+    const DYNAMIC_ELF: &[u8] = include_bytes!("../test-data/dynamic.elf");
+
+    #[test]
+    fn dynamic_info() {
+        let info = read_dynamic_info(Cursor::new(DYNAMIC_ELF)).unwrap();
+
+        assert_eq!(
+            DynamicInfo {
+                libraries: vec!["libc.so.6".into(), "libcurl.so.4".into()],
+                symbol_versions: vec![
+                    "GLIBC_2.2.5".into(),
+                    "GLIBC_2.28".into(),
+                    "GLIBC_2.3".into(),
+                    "GLIBC_PRIVATE".into(),
+                ],
+            },
+            info
+        );
+
+        assert_eq!(None, read_dynamic_info(Cursor::new(b"#!/bin/sh\n")));
+    }
+
+    #[test]
+    fn malformed_elf() {
+        // Program headers that are out of bounds.
+        let mut elf = DYNAMIC_ELF.to_vec();
+        elf[0x20..0x28].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(None, read_dynamic_info(Cursor::new(elf)));
+
+        // A dynamic section that is out of bounds.
+        let mut elf = DYNAMIC_ELF.to_vec();
+        elf[0x80..0x88].copy_from_slice(&(u64::MAX - 4).to_le_bytes());
+        assert_eq!(None, read_dynamic_info(Cursor::new(elf)));
+
+        // A truncated file.
+        let elf = &DYNAMIC_ELF[..DYNAMIC_ELF.len() - 16];
+        assert_eq!(None, read_dynamic_info(Cursor::new(elf)));
+    }
+
+    #[test]
+    fn glibc_versions() {
+        assert_eq!(Some((2, 17)), parse_glibc_version("2.17"));
+        assert_eq!(Some((2, 2)), parse_glibc_version("2.2.5"));
+        assert_eq!(None, parse_glibc_version("PRIVATE"));
+
+        let info = read_dynamic_info(Cursor::new(DYNAMIC_ELF)).unwrap();
+        assert_eq!(Some((2, 28)), required_glibc_version(&info.symbol_versions));
+        assert_eq!(None, required_glibc_version(&[]));
+    }
+
+    #[test]
+    fn library_hints() {
+        assert!(library_hint("libcurl.so.4").contains("libcurl4"));
+        assert!(library_hint("libssl.so.1.1").contains("OpenSSL 1.1"));
+        assert_eq!(
+            "install the package that provides libfoo.so.2",
+            library_hint("libfoo.so.2")
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    env,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    Monger,
+};
+
+/// The environment that puts the binaries of a version first on the PATH.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionEnv {
+    /// The ID of the version, which should be exported as `MONGER_VERSION`.
+    pub version: String,
+
+    /// The directories of the new PATH, in order.
+    pub path: Vec<PathBuf>,
+}
+
+/// Replaces any directory inside `versions_dir` in a PATH with `bin_dir`, which is put first. If
+/// `bin_dir` is `None`, the directories inside `versions_dir` are only removed.
+fn replace_bin_dir(path: &OsStr, versions_dir: &Path, bin_dir: Option<PathBuf>) -> Vec<PathBuf> {
+    bin_dir
+        .into_iter()
+        .chain(env::split_paths(path).filter(|dir| !dir.starts_with(versions_dir)))
+        .collect()
+}
+
+impl Monger {
+    /// Determines the environment that puts the binaries of a version first on the given PATH,
+    /// replacing the binaries of any other version put there before. For the "system" version,
+    /// the binaries of other versions are only removed.
+    pub fn version_env(&self, id: &str, path: &OsStr) -> Result<VersionEnv> {
+        let not_found = || Error::VersionNotFound { version: id.into() };
+
+        if self.fs.is_system(id) {
+            return Ok(VersionEnv {
+                version: id.into(),
+                path: replace_bin_dir(path, &self.fs.get_bin_dir(), None),
+            });
+        }
+
+        let version = self
+            .fs
+            .get_newest_matching_version(id)
+            .map_err(|_| not_found())?;
+        let bin_dir = self.fs.get_version_bin_dir(&version)?;
+
+        if !bin_dir.is_dir() {
+            return Err(not_found());
+        }
+
+        Ok(VersionEnv {
+            version,
+            path: replace_bin_dir(path, &self.fs.get_bin_dir(), Some(bin_dir)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, path::PathBuf};
+
+    use super::replace_bin_dir;
+
+    #[test]
+    fn replace_previous_version() {
+        let versions_dir = PathBuf::from("/home/me/.monger/mongodb-versions");
+        let path = OsStr::new("/usr/bin:/home/me/.monger/mongodb-versions/4.2.8/bin:/bin");
+
+        assert_eq!(
+            vec![
+                versions_dir.join("4.4.1/bin"),
+                PathBuf::from("/usr/bin"),
+                PathBuf::from("/bin"),
+            ],
+            replace_bin_dir(path, &versions_dir, Some(versions_dir.join("4.4.1/bin")))
+        );
+
+        assert_eq!(
+            vec![PathBuf::from("/usr/bin"), PathBuf::from("/bin")],
+            replace_bin_dir(path, &versions_dir, None)
+        );
+    }
+}
@@ -4,18 +4,60 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Error)]
 pub enum Error {
+    #[error("No alias named '{name}' exists")]
+    AliasNotFound { name: String },
+
     #[error(
         "Unable to find {binary} to execute. Run `monger get {version}` and try again if you're \
          sure the version and binary name are correct"
     )]
     BinaryNotFound { binary: String, version: String },
 
+    #[error("No data directory named '{name}' exists")]
+    DataNotFound { name: String },
+
+    #[error(
+        "`--dbpath` was passed as a mongod argument along with `--data`; only one of them can be \
+         used"
+    )]
+    DataWithDbPath,
+
+    #[error("The database files in {path} are in use by a mongod with PID {pid}; stop it first")]
+    DatabaseInUse { path: String, pid: u32 },
+
+    #[error(
+        "Deployment '{name}' differs from its definition. Pass --recreate to replace it, which \
+         deletes its data."
+    )]
+    DeploymentChanged { name: String },
+
+    #[error("No deployment named '{name}' exists")]
+    DeploymentNotFound { name: String },
+
+    #[error("Deployment '{name}' is not running. Run `monger up` to restart it")]
+    DeploymentNotRunning { name: String },
+
+    #[error("A deployment named '{name}' already exists")]
+    ExistingDeployment { name: String },
+
     #[error(
         "A mongodb version with the id '{id}' already exists. Either pass --force to overwrite it \
          or pass a different id."
     )]
     ExistingId { id: String },
 
+    #[error(
+        "A snapshot named '{name}' of version {version} already exists. Pass --force to overwrite \
+         it."
+    )]
+    ExistingSnapshot { version: String, name: String },
+
+    #[error("Failed to start {member}; see {log_path} for details")]
+    FailedMemberStart { member: String, log_path: String },
+
+    #[error("Command against port {port} failed: {output}")]
+    FailedShellCommand { port: u16, output: String },
+
     #[error("`{command}` command failed")]
     FailedSubprocess {
         command: String,
@@ -31,36 +73,122 @@ pub enum Error {
     #[error("HTML response from {url} did not match expected structure")]
     InvalidHtml { url: String },
 
+    #[error("Unable to start {version} with data directory '{name}': {reason}")]
+    IncompatibleData {
+        name: String,
+        version: String,
+        reason: String,
+    },
+
+    #[error("Invalid alias '{name}': {reason}")]
+    InvalidAlias { name: String, reason: String },
+
+    #[error("Invalid configuration: {message}")]
+    InvalidConfig { message: String },
+
+    #[error("Invalid data directory name '{name}'")]
+    InvalidDataName { name: String },
+
+    #[error("Invalid snapshot name '{name}'")]
+    InvalidSnapshotName { name: String },
+
+    #[error("Invalid topology: {message}")]
+    InvalidTopology { message: String },
+
+    #[error("Unable to upgrade deployment '{name}' to {version}: {reason}")]
+    InvalidUpgrade {
+        name: String,
+        version: String,
+        reason: String,
+    },
+
     #[error("HTTP error occurred when trying to access url '{url}'")]
     InvalidUrl { url: String },
 
     #[error("MongoDB version {version} does not exist")]
     InvalidVersion { version: String },
 
+    #[error("Invalid version file {path}: {reason}")]
+    InvalidVersionFile { path: String, reason: String },
+
     #[error("An I/O error occurred: {inner}")]
     Io {
         #[from]
         inner: std::io::Error,
     },
 
+    #[error("Timed out waiting for the lock on {lock} held by {holder}")]
+    LockTimeout { lock: String, holder: String },
+
+    #[error(
+        "Version {version} is required to upgrade the data directory. Run `monger get {version}` \
+         and try again"
+    )]
+    MissingUpgradeVersion { version: String },
+
+    #[error("There are no database files for {name} to snapshot; start a mongod with them first")]
+    NoDatabaseFiles { name: String },
+
+    #[error(
+        "No MongoDB version was given, and none is set by $MONGER_VERSION, a .monger-version \
+         file in the current directory or its parents, or `monger config set versions.default`"
+    )]
+    NoPinnedVersion,
+
+    #[error("Replica set '{name}' has no primary")]
+    NoPrimary { name: String },
+
+    #[error("Unable to find a MongoDB shell (`mongo` or `mongosh`) for version {version}")]
+    NoShell { version: String },
+
+    #[error("Deployment '{name}' is not a replica set")]
+    NotReplicaSet { name: String },
+
     #[error("Unable to determine the OS release version")]
     OsRelease {
         #[from]
         inner: rs_release::OsReleaseError,
     },
 
+    #[error("Port {port} is already in use")]
+    PortInUse { port: u16 },
+
+    #[error("Timed out waiting for process {pid} to exit")]
+    ProcessTimeout { pid: u32 },
+
     #[error("Unable to parse semantic version")]
     SemVer {
         #[from]
         inner: semver::SemVerError,
     },
 
+    #[error("No snapshot named '{name}' of version {version} exists")]
+    SnapshotNotFound { version: String, name: String },
+
+    #[error("Unable to generate TLS certificates: {message}")]
+    Tls { message: String },
+
+    #[error("Unable to parse TOML: {inner}")]
+    TomlDeserialize {
+        #[from]
+        inner: toml::de::Error,
+    },
+
+    #[error("Unable to serialize TOML: {inner}")]
+    TomlSerialize {
+        #[from]
+        inner: toml::ser::Error,
+    },
+
     #[error("Unable to convert HTTP header to string: {inner}")]
     ToStr {
         #[from]
         inner: reqwest::header::ToStrError,
     },
 
+    #[error("The version that last used data directory '{name}' is unknown")]
+    UnknownDataVersion { name: String },
+
     #[error("Unable to find home directory")]
     UnknownHomeDirectory,
 
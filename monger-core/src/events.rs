@@ -0,0 +1,234 @@
+use std::{
+    fmt::{self, Debug, Display},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use serde::Serialize;
+
+/// Progress reported while monger works. monger-core never writes to stdout itself; instead, each
+/// event is passed to the handler set with `Monger::set_event_handler`, and its `Display` output
+/// is the message monger's CLI prints for it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A shard is being added to a cluster.
+    AddingShard { host: String },
+
+    /// A version that an alias referred to was deleted.
+    AliasedVersionDeleted { alias: String, version: String },
+
+    /// Temporary files left over from an installation are being deleted.
+    CleaningUp,
+
+    /// A data directory is being compressed into a snapshot.
+    Compressing { path: PathBuf },
+
+    /// The admin user of a deployment with auth is being created.
+    CreatingUser { username: String },
+
+    /// An archive is being extracted.
+    Decompressing { path: PathBuf },
+
+    /// Part of a release has been downloaded, in bytes. The total size is unknown if the server
+    /// didn't give it.
+    DownloadProgress {
+        version: String,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+
+    /// A release or custom build is being downloaded.
+    Downloading { url: String },
+
+    /// A mongod is being started with a data directory it can't safely open because `--force` was
+    /// passed.
+    ForcingIncompatibleData {
+        version: String,
+        name: String,
+        reason: String,
+    },
+
+    /// The TLS certificates used by deployments are being generated.
+    GeneratingCertificates,
+
+    /// A replica set's configuration is being initiated.
+    InitiatingReplicaSet { name: String },
+
+    /// One of several versions being installed at once failed to install.
+    InstallFailed { version: String, error: String },
+
+    /// One of several versions being installed at once was installed, or already had been.
+    Installed { id: String, downloaded: bool },
+
+    /// A member of a deployment that isn't running is being started again.
+    RestartingMember { name: String, port: u16 },
+
+    /// A mongod is being started without auth to create the initial user.
+    StartingForUserCreation,
+
+    /// A member of a deployment is being started.
+    StartingMember { name: String, port: u16 },
+
+    /// A member of a deployment couldn't be stopped.
+    StopFailed { name: String, error: String },
+
+    /// A member of a deployment is being stopped.
+    StoppingMember { name: String },
+
+    /// More versions are installed than `cache.max_versions` allows.
+    TooManyVersions { installed: usize, max: usize },
+
+    /// A data directory is being upgraded to a release with the given version.
+    UpgradingData {
+        name: String,
+        release: String,
+        version: String,
+    },
+
+    /// A step of a rolling upgrade is starting.
+    UpgradeStep {
+        step: usize,
+        steps: usize,
+        description: String,
+    },
+
+    /// Another monger process holds a lock that is needed, so this one is waiting for it to be
+    /// released. The PID is unknown if the lock file doesn't record a running process.
+    WaitingForLock { lock: String, pid: Option<u32> },
+
+    /// A download is being written to disk.
+    Writing { path: PathBuf },
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::AddingShard { host } => write!(f, "adding shard {}...", host),
+            Event::AliasedVersionDeleted { alias, version } => write!(
+                f,
+                "warning: alias '{}' referred to {}, which is no longer installed",
+                alias, version
+            ),
+            Event::CleaningUp => write!(f, "cleaning up..."),
+            Event::Compressing { path } => write!(f, "compressing {}...", path.display()),
+            Event::CreatingUser { username } => write!(f, "creating user {}...", username),
+            Event::Decompressing { path } => write!(f, "decompressing {}...", path.display()),
+            Event::DownloadProgress {
+                version,
+                downloaded,
+                total: Some(total),
+            } => write!(
+                f,
+                "{}: downloaded {} of {}",
+                version,
+                megabytes(*downloaded),
+                megabytes(*total)
+            ),
+            Event::DownloadProgress {
+                version,
+                downloaded,
+                total: None,
+            } => write!(f, "{}: downloaded {}", version, megabytes(*downloaded)),
+            Event::Downloading { url } => write!(f, "downloading {}...", url),
+            Event::ForcingIncompatibleData {
+                version,
+                name,
+                reason,
+            } => write!(
+                f,
+                "warning: starting {} with data directory '{}' even though {}",
+                version, name, reason
+            ),
+            Event::GeneratingCertificates => write!(f, "generating TLS certificates..."),
+            Event::InitiatingReplicaSet { name } => {
+                write!(f, "initiating replica set {}...", name)
+            }
+            Event::InstallFailed { version, error } => {
+                write!(f, "unable to install {}: {}", version, error)
+            }
+            Event::Installed {
+                id,
+                downloaded: true,
+            } => write!(f, "installed {}", id),
+            Event::Installed {
+                id,
+                downloaded: false,
+            } => write!(f, "{} is already installed", id),
+            Event::RestartingMember { name, port } => {
+                write!(f, "restarting {} on port {}...", name, port)
+            }
+            Event::StartingForUserCreation => {
+                write!(f, "starting mongod without auth to create user...")
+            }
+            Event::StartingMember { name, port } => {
+                write!(f, "starting {} on port {}...", name, port)
+            }
+            Event::StopFailed { name, error } => write!(f, "unable to stop {}: {}", name, error),
+            Event::StoppingMember { name } => write!(f, "stopping {}...", name),
+            Event::TooManyVersions { installed, max } => write!(
+                f,
+                "{} versions are installed, which is more than the {} allowed by \
+                 cache.max_versions; consider running `monger prune`",
+                installed, max
+            ),
+            Event::UpgradingData {
+                name,
+                release,
+                version,
+            } => write!(
+                f,
+                "upgrading data directory {} to {} with {}...",
+                name, release, version
+            ),
+            Event::UpgradeStep {
+                step,
+                steps,
+                description,
+            } => write!(f, "[{}/{}] {}...", step, steps, description),
+            Event::WaitingForLock {
+                lock,
+                pid: Some(pid),
+            } => write!(f, "waiting for lock on {} held by PID {}...", lock, pid),
+            Event::WaitingForLock { lock, pid: None } => {
+                write!(f, "waiting for lock on {}...", lock)
+            }
+            Event::Writing { path } => write!(f, "writing {}...", path.display()),
+        }
+    }
+}
+
+/// Formats a size in bytes in megabytes.
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+type Handler = dyn Fn(&Event) + Send + Sync;
+
+/// Passes events to the handler set by the user of the library, if any.
+#[derive(Clone, Default)]
+pub(crate) struct Events {
+    handler: Option<Arc<Handler>>,
+}
+
+impl Debug for Events {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Events")
+            .field("handler", &self.handler.as_ref().map(|_| "<handler>"))
+            .finish()
+    }
+}
+
+impl Events {
+    pub(crate) fn new(handler: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        Self {
+            handler: Some(Arc::new(handler)),
+        }
+    }
+
+    pub(crate) fn emit(&self, event: Event) {
+        if let Some(ref handler) = self.handler {
+            handler(&event);
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use std::path::Path;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::Version;
+
+use crate::{
+    error::{Error, Result},
+    events::Event,
+    fs::DataMetadata,
+    process::run_command_with_output,
+    util::parse_major_minor_version,
+    Monger,
+};
+
+/// The releases before 5.0 that a data directory has to be upgraded through one at a time. From
+/// 5.0 onwards, each major version is a release (and the quarterly rapid releases in between can
+/// be skipped).
+const LEGACY_RELEASES: [(u64, u64); 7] = [(3, 0), (3, 2), (3, 4), (3, 6), (4, 0), (4, 2), (4, 4)];
+
+/// The file that mongod creates in every database directory it has opened.
+const STORAGE_METADATA_FILE: &str = "storage.bson";
+
+lazy_static! {
+    static ref MONGOD_VERSION_REGEX: Regex = Regex::new(r"db version v(\S+)").unwrap();
+}
+
+/// The position of the release that a major and minor version belongs to in the upgrade path.
+fn release_index((major, minor): (u64, u64)) -> usize {
+    if major >= 5 {
+        return LEGACY_RELEASES.len() + (major - 5) as usize;
+    }
+
+    LEGACY_RELEASES
+        .iter()
+        .rposition(|release| *release <= (major, minor))
+        .unwrap_or(0)
+}
+
+fn release(index: usize) -> (u64, u64) {
+    match LEGACY_RELEASES.get(index) {
+        Some(release) => *release,
+        None => (5 + (index - LEGACY_RELEASES.len()) as u64, 0),
+    }
+}
+
+fn format_release((major, minor): (u64, u64)) -> String {
+    format!("{}.{}", major, minor)
+}
+
+/// The featureCompatibilityVersion that the files in a data directory are known to be at most. If
+/// the FCV wasn't recorded, the version of the server that last opened the files is used instead.
+fn recorded_fcv(metadata: &DataMetadata) -> Option<(u64, u64)> {
+    metadata
+        .feature_compatibility_version
+        .as_deref()
+        .and_then(parse_major_minor_version)
+        .or_else(|| {
+            metadata
+                .server_version
+                .as_deref()
+                .and_then(|version| Version::parse(version).ok())
+                .map(|version| (version.major, version.minor))
+        })
+}
+
+/// Checks whether a server of the given version can open database files with the given metadata,
+/// returning the reason if it can't.
+fn check_compatibility(server: &Version, metadata: &DataMetadata) -> Option<String> {
+    let fcv = recorded_fcv(metadata)?;
+    let server = (server.major, server.minor);
+
+    if server < fcv {
+        return Some(format!(
+            "its featureCompatibilityVersion is {}, so it can't be opened by {} without first \
+             setting the featureCompatibilityVersion to {}",
+            format_release(fcv),
+            format_release(server),
+            format_release(server)
+        ));
+    }
+
+    if release_index(server) > release_index(fcv) + 1 {
+        return Some(format!(
+            "its featureCompatibilityVersion is {}, so upgrading it to {} skips {}; use `monger \
+             upgrade-data` to upgrade it through each release",
+            format_release(fcv),
+            format_release(server),
+            format_release(release(release_index(fcv) + 1))
+        ));
+    }
+
+    None
+}
+
+/// Checks whether a server running `from` with the given featureCompatibilityVersion can be
+/// restarted on `to` without going through an intermediate release, returning the reason if it
+/// can't.
+pub(crate) fn check_upgrade(
+    from: Option<&Version>,
+    fcv: Option<(u64, u64)>,
+    to: &Version,
+) -> Option<String> {
+    let metadata = DataMetadata {
+        server_version: from.map(ToString::to_string),
+        feature_compatibility_version: fcv.map(format_release),
+        ..Default::default()
+    };
+
+    check_compatibility(to, &metadata)
+}
+
+/// The admin command that sets the featureCompatibilityVersion to the given release.
+pub(crate) fn set_fcv_command(release: (u64, u64)) -> String {
+    // Starting in 7.0, setting the featureCompatibilityVersion requires confirmation since it
+    // can't be downgraded afterwards.
+    if release >= (7, 0) {
+        format!(
+            "{{setFeatureCompatibilityVersion: '{}', confirm: true}}",
+            format_release(release)
+        )
+    } else {
+        format!(
+            "{{setFeatureCompatibilityVersion: '{}'}}",
+            format_release(release)
+        )
+    }
+}
+
+impl Monger {
+    /// Determines the server version of an installed MongoDB version. Version IDs that aren't
+    /// server versions themselves (e.g. "system" or custom IDs) are resolved by running
+    /// `mongod --version`.
+    pub(crate) fn server_version(&self, version: &str) -> Option<Version> {
+        if let Some(version) = self
+            .fs
+            .get_newest_matching_version(version)
+            .ok()
+            .and_then(|matched| Version::parse(&matched).ok())
+        {
+            return Some(version);
+        }
+
+        let mongod = if self.fs.is_system(version) {
+            "mongod".into()
+        } else {
+            self.fs.get_version_bin_dir(version).ok()?.join("mongod")
+        };
+
+        let output = run_command_with_output(
+            mongod.to_string_lossy().as_ref(),
+            vec!["--version"],
+            std::env::current_dir().ok()?,
+        )
+        .ok()?;
+
+        MONGOD_VERSION_REGEX
+            .captures(&String::from_utf8_lossy(&output.stdout))
+            .and_then(|captures| Version::parse(&captures[1]).ok())
+    }
+
+    /// Checks that a mongod of the given version can safely open the database files in a data
+    /// directory managed by monger, returning the metadata to record in it once the mongod has
+    /// started.
+    pub(crate) fn check_data_dir(
+        &self,
+        data_dir: &Path,
+        version: &str,
+        force: bool,
+    ) -> Result<Option<DataMetadata>> {
+        if !self.fs.is_managed_data_dir(data_dir) {
+            return Ok(None);
+        }
+
+        self.fs.check_db_dir_unused(data_dir)?;
+
+        let mut metadata = self.fs.read_data_metadata(data_dir)?;
+        let server_version = self.server_version(version);
+
+        // If mongod never created its files (e.g. it exited on startup), the recorded metadata
+        // describes files that don't exist, so there's nothing for it to be incompatible with.
+        let opened = data_dir.join(STORAGE_METADATA_FILE).exists();
+
+        if let Some(server_version) = server_version.as_ref().filter(|_| opened) {
+            if let Some(reason) = check_compatibility(server_version, &metadata) {
+                let name = data_dir
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+
+                if !force {
+                    return Err(Error::IncompatibleData {
+                        name,
+                        version: version.into(),
+                        reason: format!("{}. Pass --force to start it anyway", reason),
+                    });
+                }
+
+                self.events.emit(Event::ForcingIncompatibleData {
+                    version: version.into(),
+                    name,
+                    reason,
+                });
+            }
+        }
+
+        // A new data directory starts out with the featureCompatibilityVersion of the server that
+        // creates it; upgrading the server afterwards leaves it unchanged.
+        if !opened {
+            metadata.feature_compatibility_version = server_version
+                .as_ref()
+                .map(|version| format_release((version.major, version.minor)));
+        }
+
+        metadata.version = Some(
+            self.fs
+                .get_newest_matching_version(version)
+                .unwrap_or_else(|_| version.into()),
+        );
+        metadata.server_version = server_version.map(|version| version.to_string());
+
+        Ok(Some(metadata))
+    }
+
+    /// Upgrades the database files in a data directory so that they can be opened by the given
+    /// version, starting a mongod of each intermediate release in turn and setting the
+    /// featureCompatibilityVersion to that release. Each intermediate release must be installed.
+    pub fn upgrade_data(&self, name: &str, to: &str) -> Result<()> {
+        let data_dir = self.fs.get_existing_data_dir(name)?;
+        self.fs.check_db_dir_unused(&data_dir)?;
+
+        let metadata = self.fs.read_data_metadata(&data_dir)?;
+        let fcv = recorded_fcv(&metadata)
+            .ok_or_else(|| Error::UnknownDataVersion { name: name.into() })?;
+
+        let target = self
+            .server_version(to)
+            .map(|version| (version.major, version.minor))
+            .ok_or_else(|| Error::VersionNotFound { version: to.into() })?;
+
+        if target < fcv {
+            return Err(Error::IncompatibleData {
+                name: name.into(),
+                version: to.into(),
+                reason: format!(
+                    "its featureCompatibilityVersion is {}, and `monger upgrade-data` can't \
+                     downgrade it",
+                    format_release(fcv)
+                ),
+            });
+        }
+
+        // If the FCV wasn't recorded, it may still be at the previous release.
+        let first = if metadata.feature_compatibility_version.is_some() {
+            release_index(fcv) + 1
+        } else {
+            release_index(fcv)
+        };
+        let last = release_index(target);
+
+        // Every intermediate release needs to be installed, so this is checked before changing
+        // anything.
+        let steps = (first..=last)
+            .map(|index| {
+                if index == last {
+                    return Ok((to.to_string(), target));
+                }
+
+                let step = release(index);
+                let id = self
+                    .fs
+                    .get_newest_matching_version(&format_release(step))
+                    .map_err(|_| Error::MissingUpgradeVersion {
+                        version: format_release(step),
+                    })?;
+
+                Ok((id, step))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (id, step) in steps {
+            let fcv = format_release(step);
+            let command = set_fcv_command(step);
+
+            self.events.emit(Event::UpgradingData {
+                name: name.into(),
+                release: fcv.clone(),
+                version: id.clone(),
+            });
+
+            self.with_temporary_mongod(
+                &id,
+                vec!["--dbpath".into(), data_dir.to_string_lossy().into_owned()],
+                |target| self.run_admin_command(target, &command),
+            )?;
+
+            let mut metadata = self.fs.read_data_metadata(&data_dir)?;
+            metadata.feature_compatibility_version = Some(fcv);
+            self.fs.write_data_metadata(&data_dir, &metadata)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::{check_compatibility, check_upgrade, release, release_index, set_fcv_command};
+    use crate::fs::DataMetadata;
+
+    fn metadata(server_version: &str, fcv: Option<&str>) -> DataMetadata {
+        DataMetadata {
+            version: Some(server_version.into()),
+            server_version: Some(server_version.into()),
+            feature_compatibility_version: fcv.map(Into::into),
+        }
+    }
+
+    fn check(server: &str, metadata: &DataMetadata) -> bool {
+        check_compatibility(&Version::parse(server).unwrap(), metadata).is_none()
+    }
+
+    #[test]
+    fn releases() {
+        assert_eq!(release_index((4, 2)) + 1, release_index((4, 4)));
+        assert_eq!(release_index((4, 4)) + 1, release_index((5, 0)));
+        assert_eq!(release_index((5, 0)), release_index((5, 3)));
+        assert_eq!(release_index((5, 3)) + 1, release_index((6, 0)));
+        assert_eq!((5, 0), release(release_index((4, 4)) + 1));
+        assert_eq!((8, 0), release(release_index((8, 0))));
+    }
+
+    #[test]
+    fn allow_single_release_upgrade() {
+        assert!(check("4.4.1", &metadata("4.2.8", Some("4.2"))));
+        assert!(check("5.0.14", &metadata("4.4.1", Some("4.4"))));
+        assert!(check("6.0.2", &metadata("5.0.14", Some("5.0"))));
+        assert!(check("4.4.1", &DataMetadata::default()));
+    }
+
+    #[test]
+    fn reject_skipped_release() {
+        assert!(!check("6.0.2", &metadata("4.2.8", Some("4.2"))));
+        assert!(!check("5.0.14", &metadata("4.4.1", Some("4.2"))));
+        assert!(!check("6.0.2", &metadata("4.4.1", None)));
+    }
+
+    #[test]
+    fn downgrades() {
+        assert!(check("4.2.8", &metadata("4.4.1", Some("4.2"))));
+        assert!(!check("4.2.8", &metadata("4.4.1", Some("4.4"))));
+        assert!(!check("4.2.8", &metadata("4.4.1", None)));
+    }
+
+    #[test]
+    fn rolling_upgrades() {
+        let version = |v| Version::parse(v).unwrap();
+
+        assert!(check_upgrade(Some(&version("4.4.1")), None, &version("5.0.14")).is_none());
+        assert!(check_upgrade(Some(&version("4.4.1")), None, &version("4.4.9")).is_none());
+        assert!(check_upgrade(Some(&version("4.4.1")), None, &version("6.0.2")).is_some());
+
+        // A server whose featureCompatibilityVersion wasn't raised after its last upgrade can't
+        // skip ahead, even if its binaries could.
+        assert!(check_upgrade(Some(&version("4.4.1")), Some((4, 4)), &version("5.0.14")).is_none());
+        assert!(check_upgrade(Some(&version("4.4.1")), Some((4, 2)), &version("5.0.14")).is_some());
+        assert!(check_upgrade(None, Some((4, 2)), &version("5.0.14")).is_some());
+    }
+
+    #[test]
+    fn fcv_commands() {
+        assert_eq!(
+            "{setFeatureCompatibilityVersion: '6.0'}",
+            set_fcv_command((6, 0))
+        );
+        assert_eq!(
+            "{setFeatureCompatibilityVersion: '7.0', confirm: true}",
+            set_fcv_command((7, 0))
+        );
+    }
+}
@@ -1,30 +1,101 @@
 use std::{
-    collections::{BinaryHeap, HashMap},
+    collections::{BTreeMap, BTreeSet},
     ffi::OsString,
-    fs::{create_dir_all, read_dir, remove_dir_all, remove_file, rename, File, OpenOptions},
-    io::{Read, Write},
+    fs::{
+        create_dir_all, metadata, read_dir, read_to_string, remove_dir_all, remove_file, rename,
+        set_permissions, File, OpenOptions, Permissions,
+    },
+    io::{ErrorKind, Read, Write},
+    os::unix::fs::{symlink, OpenOptionsExt, PermissionsExt},
     path::{Path, PathBuf},
     process::Child,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime},
 };
 
 use dirs::home_dir;
 use semver::Version;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    defaults::DefaultArgs,
     error::{Error, Result},
-    process::{exec_command, run_background_command, run_foreground_command},
-    util::{parse_major_minor_version, select_newer_version},
+    events::{Event, Events},
+    gc::{Garbage, GarbageKind},
+    info::BinaryInfo,
+    lock::{acquire, Lock, LockMode},
+    manifest::Manifest,
+    process::{exec_command, process_is_running, run_background_command, run_foreground_command},
+    spec::{ReleaseCatalog, VersionSpec},
 };
 
 const DEFAULT_HOME_DIR: &str = ".monger";
 const DEFAULT_BIN_DIR: &str = "mongodb-versions";
 const DEFAULT_DB_DIR: &str = "db";
+const DEFAULT_DEPLOYMENTS_DIR: &str = "deployments";
+const DEPLOYMENT_STATE_FILE: &str = "state.toml";
+const DEFAULT_TLS_DIR: &str = "tls";
+const SHELL_SCRIPTS_DIR: &str = "scripts";
+const DEFAULT_SNAPSHOTS_DIR: &str = "snapshots";
+const DEFAULT_SHIMS_DIR: &str = "shims";
+const LOCKS_DIR: &str = "locks";
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+const SNAPSHOT_EXTENSION: &str = ".tar.gz";
+const MONGOD_LOCK_FILE: &str = "mongod.lock";
+const DATA_METADATA_FILE: &str = ".monger.toml";
+pub(crate) const MANIFEST_FILE: &str = ".monger-install.toml";
+const CUSTOM_DOWNLOAD_PREFIX: &str = "custom-download-";
+const ARCHIVE_EXTENSIONS: &[&str] = &[".tgz", ".tar.gz", ".zip"];
+const DEFAULT_ARGS_FILE: &str = "default-args.toml";
+const LEGACY_DEFAULT_ARGS_FILE: &str = "default-args";
+
+/// A directory of database files managed by monger, either named after the version that uses it
+/// by default or given a name with `--data`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataDir {
+    pub name: String,
+
+    /// The ID of the MongoDB version that last started a mongod with the directory, if any.
+    pub version: Option<String>,
+}
+
+/// The metadata that monger stores alongside the database files in a data directory.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct DataMetadata {
+    /// The ID of the MongoDB version that last started a mongod with the directory.
+    pub(crate) version: Option<String>,
+
+    /// The server version of that mongod (e.g. "4.4.1"), which may differ from its ID.
+    pub(crate) server_version: Option<String>,
+
+    /// The featureCompatibilityVersion of the database files (e.g. "4.4").
+    pub(crate) feature_compatibility_version: Option<String>,
+}
+
+/// A compressed copy of the database files of a MongoDB version.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// The version or named data directory whose database files were saved.
+    pub version: String,
+
+    /// The name the snapshot was saved with.
+    pub name: String,
+
+    /// The size of the compressed snapshot in bytes.
+    pub size: u64,
+}
 
 #[derive(Debug)]
 pub struct Fs {
     home_dir: PathBuf,
     bin_dir: PathBuf,
     db_dir: PathBuf,
+    deployments_dir: PathBuf,
+    snapshots_dir: PathBuf,
+    aliases: BTreeMap<String, String>,
+    lock_timeout: Duration,
+    prereleases: bool,
+    events: Events,
 }
 
 impl Fs {
@@ -48,7 +119,7 @@ impl Fs {
     }
 
     #[inline]
-    fn get_bin_dir(&self) -> PathBuf {
+    pub(crate) fn get_bin_dir(&self) -> PathBuf {
         self.get_file(self.bin_dir.as_path())
     }
 
@@ -62,7 +133,17 @@ impl Fs {
         self.get_bin_file_abs(version)
     }
 
-    fn get_version_bin_dir(&self, version: &str) -> Result<PathBuf> {
+    #[inline]
+    fn get_deployments_dir(&self) -> PathBuf {
+        self.get_file(self.deployments_dir.as_path())
+    }
+
+    #[inline]
+    pub(crate) fn get_deployment_dir(&self, name: &str) -> PathBuf {
+        self.get_deployments_dir().join(name)
+    }
+
+    pub(crate) fn get_version_bin_dir(&self, version: &str) -> Result<PathBuf> {
         let matched_version_str = self.get_newest_matching_version(version)?;
         let matched_version_path = Path::new(&matched_version_str);
         let path = self.get_bin_file_abs(matched_version_path.join("bin"));
@@ -76,13 +157,76 @@ impl Fs {
     }
 
     pub fn clear_db_dir(&self, version: &str) -> Result<bool> {
+        let version = self.resolve_alias(version);
         let db_dir = self.get_db_file_rel(version);
         let found = self.delete_directory(db_dir)?;
 
         Ok(found)
     }
 
-    fn get_newest_matching_version(&self, version: &str) -> Result<String> {
+    /// The version ID that an alias refers to, or the ID itself if it isn't an alias. This is the
+    /// one place aliases are resolved, so it has to be called before looking up any version.
+    pub(crate) fn resolve_alias<'a>(&'a self, id: &'a str) -> &'a str {
+        self.aliases.get(id).map_or(id, String::as_str)
+    }
+
+    /// Whether an ID refers to the "system" version, i.e. the binaries on the PATH, either directly
+    /// or through an alias.
+    pub(crate) fn is_system(&self, id: &str) -> bool {
+        self.resolve_alias(id) == "system"
+    }
+
+    pub(crate) fn set_aliases(&mut self, aliases: BTreeMap<String, String>) {
+        self.aliases = aliases;
+    }
+
+    pub(crate) fn set_events(&mut self, events: Events) {
+        self.events = events;
+    }
+
+    /// Locks the versions directory as a whole: shared to write downloads to it, or exclusively to
+    /// clean up after failed installations without mistaking one in progress for one.
+    pub(crate) fn lock_versions_dir(&self, mode: LockMode) -> Result<Lock> {
+        acquire(
+            &self.get_file(LOCKS_DIR).join("versions.lock"),
+            "the versions directory",
+            mode,
+            self.lock_timeout,
+            &self.events,
+        )
+    }
+
+    /// Locks the archive a release is downloaded to and the directory it's extracted into, which
+    /// are shared by every installation of the release regardless of its ID.
+    fn lock_download(&self, filename: &str) -> Result<Lock> {
+        acquire(
+            &self
+                .get_file(LOCKS_DIR)
+                .join(format!("download-{}.lock", filename)),
+            &format!("download {}", filename),
+            LockMode::Exclusive,
+            self.lock_timeout,
+            &self.events,
+        )
+    }
+
+    /// Locks an installed version: exclusively to install or delete it, or shared to run its
+    /// binaries.
+    pub(crate) fn lock_version(&self, version: &str, mode: LockMode) -> Result<Lock> {
+        acquire(
+            &self
+                .get_file(LOCKS_DIR)
+                .join(format!("version-{}.lock", version)),
+            &format!("version {}", version),
+            mode,
+            self.lock_timeout,
+            &self.events,
+        )
+    }
+
+    pub(crate) fn get_newest_matching_version(&self, version: &str) -> Result<String> {
+        let version = self.resolve_alias(version);
+
         if version == "system" {
             return Ok(version.to_string());
         }
@@ -91,48 +235,33 @@ impl Fs {
             return Ok(version.to_string());
         }
 
-        let (target_major, target_minor) = match parse_major_minor_version(version) {
-            Some(pair) => pair,
-            None => {
-                return Err(Error::InvalidVersion {
-                    version: version.to_string(),
-                })
-            }
+        let not_found = || Error::InvalidVersion {
+            version: version.to_string(),
         };
-
-        let mut newest_patch = None;
-
-        for e in read_dir(self.get_bin_dir())? {
-            let entry = e?;
-
-            if !entry.file_type()?.is_dir() {
-                continue;
-            }
-
-            let v = match Version::parse(&entry.file_name().to_string_lossy()) {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
-
-            if v.major == target_major
-                && v.minor == target_minor
-                && v.build.is_empty()
-                && v.pre.is_empty()
-            {
-                newest_patch = Some(select_newer_version(newest_patch, v));
+        let spec = VersionSpec::parse(version).ok_or_else(not_found)?;
+        let installed: Vec<_> = self
+            .list_versions()?
+            .iter()
+            .filter_map(|name| Version::parse(&name.to_string_lossy()).ok())
+            .collect();
+
+        // The kinds of releases recorded at install time are only needed for specs like `lts`.
+        let mut catalog = ReleaseCatalog::default();
+
+        if spec.needs_catalog() {
+            for version in &installed {
+                if let Some(kind) = self
+                    .read_manifest(&version.to_string())?
+                    .and_then(|manifest| manifest.kind)
+                {
+                    catalog.insert(version.clone(), kind);
+                }
             }
         }
 
-        let matching_version = match newest_patch {
-            Some(version) => format!("{}", version),
-            None => {
-                return Err(Error::InvalidVersion {
-                    version: version.to_string(),
-                })
-            }
-        };
-
-        Ok(matching_version)
+        spec.select(&installed, &catalog, self.prereleases)
+            .map(ToString::to_string)
+            .ok_or_else(not_found)
     }
 
     fn create(&self) -> Result<()> {
@@ -142,36 +271,37 @@ impl Fs {
         Ok(())
     }
 
-    fn get_default_args_file(&self) -> PathBuf {
-        self.home_dir.join("default-args")
-    }
-
-    pub(crate) fn clear_default_args(&self) -> Result<bool> {
-        let default_args_file = self.get_default_args_file();
-
-        if !default_args_file.exists() {
-            return Ok(false);
+    /// Reads the default arguments used when starting a mongod. Default arguments set by older
+    /// versions of monger are stored as a single string; these are migrated to the global default
+    /// arguments the first time they are read.
+    pub(crate) fn read_default_args(&self) -> Result<DefaultArgs> {
+        match read_to_string(self.get_file(DEFAULT_ARGS_FILE)) {
+            Ok(contents) => return Ok(toml::from_str(&contents)?),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
         }
 
-        remove_file(default_args_file)?;
-        Ok(true)
-    }
+        let legacy_file = self.get_file(LEGACY_DEFAULT_ARGS_FILE);
 
-    pub(crate) fn get_default_args(&self) -> Result<Option<String>> {
-        let default_args_file = self.get_default_args_file();
+        let legacy_args = match read_to_string(&legacy_file) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(Default::default()),
+            Err(e) => return Err(e.into()),
+        };
 
-        if !default_args_file.is_file() {
-            return Ok(None);
-        }
+        let default_args = DefaultArgs {
+            global: legacy_args.split_whitespace().map(Into::into).collect(),
+            ..Default::default()
+        };
 
-        let mut default_args = String::new();
-        File::open(default_args_file)?.read_to_string(&mut default_args)?;
+        self.write_default_args(&default_args)?;
+        remove_file(legacy_file)?;
 
-        Ok(Some(default_args))
+        Ok(default_args)
     }
 
-    pub(crate) fn set_default_args(&self, default_args: &str) -> Result<()> {
-        self.write_file("default-args", default_args.as_bytes())
+    pub(crate) fn write_default_args(&self, default_args: &DefaultArgs) -> Result<()> {
+        self.write_file(DEFAULT_ARGS_FILE, toml::to_string(default_args)?.as_bytes())
     }
 
     fn decompress_download<P: AsRef<Path>>(
@@ -252,8 +382,458 @@ impl Fs {
         Ok(db_dir)
     }
 
+    #[inline]
+    fn get_db_dir(&self) -> PathBuf {
+        self.get_file(self.db_dir.as_path())
+    }
+
+    /// Resolves a version ID or data directory name to the name of a directory in the database
+    /// directory. Named data directories take precedence over versions.
+    fn resolve_data_dir_name(&self, id: &str) -> Result<String> {
+        if is_valid_name(id) && self.get_db_dir().join(id).is_dir() {
+            return Ok(id.into());
+        }
+
+        self.get_newest_matching_version(id)
+    }
+
+    pub fn create_or_get_named_data_dir(&self, name: &str) -> Result<PathBuf> {
+        if !is_valid_name(name) {
+            return Err(Error::InvalidDataName { name: name.into() });
+        }
+
+        let data_dir = self.get_db_dir().join(name);
+        create_dir_all(data_dir.as_path())?;
+        Ok(data_dir)
+    }
+
+    pub(crate) fn read_data_metadata(&self, data_dir: &Path) -> Result<DataMetadata> {
+        match read_to_string(data_dir.join(DATA_METADATA_FILE)) {
+            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(Default::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub(crate) fn write_data_metadata(
+        &self,
+        data_dir: &Path,
+        metadata: &DataMetadata,
+    ) -> Result<()> {
+        self.write_file(
+            data_dir.join(DATA_METADATA_FILE),
+            toml::to_string(metadata)?.as_bytes(),
+        )
+    }
+
+    #[inline]
+    pub(crate) fn is_managed_data_dir(&self, data_dir: &Path) -> bool {
+        data_dir.parent() == Some(self.get_db_dir().as_path())
+    }
+
+    pub fn list_data_dirs(&self) -> Result<Vec<DataDir>> {
+        let db_dir = self.get_db_dir();
+
+        if !db_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut data_dirs = Vec::new();
+
+        for e in read_dir(db_dir)? {
+            let entry = e?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            // Skips the temporary directories used when restoring snapshots.
+            if !entry.file_type()?.is_dir() || !is_valid_name(&name) {
+                continue;
+            }
+
+            data_dirs.push(DataDir {
+                name,
+                version: self.read_data_metadata(&entry.path())?.version,
+            });
+        }
+
+        data_dirs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(data_dirs)
+    }
+
+    pub(crate) fn get_existing_data_dir(&self, name: &str) -> Result<PathBuf> {
+        let data_dir = self.get_db_dir().join(name);
+
+        if !is_valid_name(name) || !data_dir.is_dir() {
+            return Err(Error::DataNotFound { name: name.into() });
+        }
+
+        Ok(data_dir)
+    }
+
+    /// Deletes the database files in a data directory while keeping the (now empty) directory.
+    pub fn clear_data_dir(&self, name: &str) -> Result<()> {
+        let data_dir = self.get_existing_data_dir(name)?;
+        self.check_db_dir_unused(&data_dir)?;
+
+        remove_dir_all(&data_dir)?;
+        create_dir_all(&data_dir)?;
+
+        Ok(())
+    }
+
+    pub fn delete_data_dir(&self, name: &str) -> Result<()> {
+        let data_dir = self.get_existing_data_dir(name)?;
+        self.check_db_dir_unused(&data_dir)?;
+
+        remove_dir_all(&data_dir)?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn get_snapshots_dir(&self) -> PathBuf {
+        self.get_file(self.snapshots_dir.as_path())
+    }
+
+    fn get_snapshot_file(&self, version: &str, name: &str) -> PathBuf {
+        self.get_snapshots_dir()
+            .join(version)
+            .join(format!("{}{}", name, SNAPSHOT_EXTENSION))
+    }
+
+    /// Fails if a mongod is currently using the database files in the given directory. mongod
+    /// writes its PID to the lock file on startup and empties it on a clean shutdown.
+    pub(crate) fn check_db_dir_unused(&self, db_dir: &Path) -> Result<()> {
+        let pid = read_to_string(db_dir.join(MONGOD_LOCK_FILE))
+            .ok()
+            .and_then(|contents| contents.trim().parse().ok());
+
+        match pid {
+            Some(pid) if process_is_running(pid) => Err(Error::DatabaseInUse {
+                path: db_dir.display().to_string(),
+                pid,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn save_snapshot(&self, version: &str, name: &str, force: bool) -> Result<Snapshot> {
+        check_snapshot_name(name)?;
+
+        let version = self.resolve_data_dir_name(version)?;
+        let db_dir = self.get_db_dir().join(&version);
+        let has_files = read_dir(&db_dir)
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(false);
+
+        if !has_files {
+            return Err(Error::NoDatabaseFiles { name: version });
+        }
+
+        self.check_db_dir_unused(&db_dir)?;
+
+        let snapshot_file = self.get_snapshot_file(&version, name);
+
+        if snapshot_file.exists() && !force {
+            return Err(Error::ExistingSnapshot {
+                version,
+                name: name.into(),
+            });
+        }
+
+        create_dir_all(self.get_snapshots_dir().join(&version))?;
+
+        // Compress into a temporary file first so that an interrupted save doesn't leave a
+        // truncated snapshot behind (or clobber the one being overwritten).
+        let temp_file = snapshot_file.with_extension(format!("tmp-{}", std::process::id()));
+
+        self.events.emit(Event::Compressing {
+            path: db_dir.clone(),
+        });
+        let result = run_foreground_command(
+            "tar",
+            vec![
+                "czf".as_ref(),
+                temp_file.as_os_str(),
+                "-C".as_ref(),
+                db_dir.as_os_str(),
+                ".".as_ref(),
+            ],
+            self.get_snapshots_dir(),
+        );
+
+        if let Err(e) = result {
+            let _ = remove_file(&temp_file);
+            return Err(e);
+        }
+
+        rename(&temp_file, &snapshot_file)?;
+
+        Ok(Snapshot {
+            version,
+            name: name.into(),
+            size: metadata(&snapshot_file)?.len(),
+        })
+    }
+
+    pub fn restore_snapshot(&self, version: &str, name: &str) -> Result<()> {
+        check_snapshot_name(name)?;
+
+        let version = self.resolve_data_dir_name(version)?;
+        let snapshot_file = self.get_snapshot_file(&version, name);
+
+        if !snapshot_file.is_file() {
+            return Err(Error::SnapshotNotFound {
+                version,
+                name: name.into(),
+            });
+        }
+
+        let db_dir = self.get_file(self.get_db_file_rel(&version));
+        self.check_db_dir_unused(&db_dir)?;
+
+        // The snapshot is extracted next to the database directory and only swapped in once it
+        // has been fully extracted, so a failed restore leaves the existing files untouched.
+        let suffix = std::process::id();
+        let restore_dir =
+            self.get_file(self.get_db_file_rel(format!(".{}.restore-{}", version, suffix)));
+        let old_dir = self.get_file(self.get_db_file_rel(format!(".{}.old-{}", version, suffix)));
+        create_dir_all(&restore_dir)?;
+
+        self.events.emit(Event::Decompressing {
+            path: snapshot_file.clone(),
+        });
+        let result = run_foreground_command(
+            "tar",
+            vec![
+                "xzf".as_ref(),
+                snapshot_file.as_os_str(),
+                "-C".as_ref(),
+                restore_dir.as_os_str(),
+            ],
+            self.get_snapshots_dir(),
+        );
+
+        if let Err(e) = result {
+            remove_dir_all(&restore_dir)?;
+            return Err(e);
+        }
+
+        let had_db_dir = db_dir.exists();
+
+        if had_db_dir {
+            if let Err(e) = rename(&db_dir, &old_dir) {
+                remove_dir_all(&restore_dir)?;
+                return Err(e.into());
+            }
+        }
+
+        if let Err(e) = rename(&restore_dir, &db_dir) {
+            if had_db_dir {
+                rename(&old_dir, &db_dir)?;
+            }
+
+            remove_dir_all(&restore_dir)?;
+            return Err(e.into());
+        }
+
+        if had_db_dir {
+            remove_dir_all(&old_dir)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn delete_snapshot(&self, version: &str, name: &str) -> Result<bool> {
+        check_snapshot_name(name)?;
+
+        let version = self.resolve_data_dir_name(version)?;
+        let snapshot_file = self.get_snapshot_file(&version, name);
+
+        if !snapshot_file.is_file() {
+            return Ok(false);
+        }
+
+        remove_file(snapshot_file)?;
+
+        Ok(true)
+    }
+
+    pub fn list_snapshots(&self, version: Option<&str>) -> Result<Vec<Snapshot>> {
+        let snapshots_dir = self.get_snapshots_dir();
+
+        if !snapshots_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let version = version
+            .map(|version| self.resolve_data_dir_name(version))
+            .transpose()?;
+
+        let mut snapshots = Vec::new();
+
+        for e in read_dir(snapshots_dir)? {
+            let version_entry = e?;
+            let snapshot_version = version_entry.file_name().to_string_lossy().into_owned();
+
+            if !version_entry.file_type()?.is_dir()
+                || version.as_ref().is_some_and(|v| *v != snapshot_version)
+            {
+                continue;
+            }
+
+            for e in read_dir(version_entry.path())? {
+                let entry = e?;
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+
+                // Skips temporary files left behind by interrupted saves.
+                let name = match file_name.strip_suffix(SNAPSHOT_EXTENSION) {
+                    Some(name) if entry.file_type()?.is_file() => name,
+                    _ => continue,
+                };
+
+                snapshots.push(Snapshot {
+                    version: snapshot_version.clone(),
+                    name: name.into(),
+                    size: entry.metadata()?.len(),
+                });
+            }
+        }
+
+        snapshots.sort_by(|a, b| (&a.version, &a.name).cmp(&(&b.version, &b.name)));
+
+        Ok(snapshots)
+    }
+
+    #[inline]
+    pub(crate) fn deployment_exists(&self, name: &str) -> bool {
+        self.get_deployment_dir(name)
+            .join(DEPLOYMENT_STATE_FILE)
+            .is_file()
+    }
+
+    pub(crate) fn create_deployment_member_dir(&self, name: &str, member: &str) -> Result<PathBuf> {
+        let member_dir = self.get_deployment_dir(name).join(member);
+        create_dir_all(member_dir.join("db"))?;
+
+        Ok(member_dir)
+    }
+
+    pub(crate) fn read_deployment_state(&self, name: &str) -> Result<String> {
+        let state_file = self.get_deployment_dir(name).join(DEPLOYMENT_STATE_FILE);
+
+        if !state_file.is_file() {
+            return Err(Error::DeploymentNotFound { name: name.into() });
+        }
+
+        let mut state = String::new();
+        File::open(state_file)?.read_to_string(&mut state)?;
+
+        Ok(state)
+    }
+
+    /// Writes the state of a deployment. Its connection string includes the root user's password
+    /// when auth is enabled, so only the owner can read it.
+    pub(crate) fn write_deployment_state(&self, name: &str, state: &str) -> Result<()> {
+        let state_file = self.get_deployment_dir(name).join(DEPLOYMENT_STATE_FILE);
+        create_dir_all(self.get_deployment_dir(name))?;
+        self.write_file(&state_file, state.as_bytes())?;
+        set_permissions(&state_file, Permissions::from_mode(0o600))?;
+
+        Ok(())
+    }
+
+    /// Writes a randomly generated keyfile for the members of a deployment to authenticate to each
+    /// other with. mongod refuses to use keyfiles that are readable by other users.
+    pub(crate) fn write_deployment_keyfile(&self, name: &str) -> Result<PathBuf> {
+        const BASE64_CHARS: &[u8] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        let mut random = [0u8; 756];
+        File::open("/dev/urandom")?.read_exact(&mut random)?;
+
+        // Each character is picked by six random bits, so that every one is equally likely.
+        let key: Vec<u8> = random
+            .iter()
+            .map(|b| BASE64_CHARS[usize::from(*b & 0b11_1111)])
+            .collect();
+
+        let keyfile = self.get_deployment_dir(name).join("keyfile");
+        create_dir_all(self.get_deployment_dir(name))?;
+        self.write_file(&keyfile, &key)?;
+        set_permissions(&keyfile, Permissions::from_mode(0o600))?;
+
+        Ok(keyfile)
+    }
+
+    #[inline]
+    pub(crate) fn get_tls_file(&self, filename: &str) -> PathBuf {
+        self.get_file(DEFAULT_TLS_DIR).join(filename)
+    }
+
+    pub(crate) fn write_tls_file(&self, filename: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.get_tls_file(filename);
+        create_dir_all(self.get_file(DEFAULT_TLS_DIR))?;
+        self.write_file(&path, bytes)?;
+        set_permissions(&path, Permissions::from_mode(0o600))?;
+
+        Ok(())
+    }
+
+    /// Writes a script for the shell to run to a file that only the owner can read. Scripts can
+    /// contain passwords, which other users could see in the process list if they were passed as
+    /// arguments.
+    pub(crate) fn write_shell_script(&self, script: &str) -> Result<PathBuf> {
+        static NEXT_SCRIPT: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = self.get_file(SHELL_SCRIPTS_DIR);
+        create_dir_all(&dir)?;
+
+        let path = dir.join(format!(
+            "eval-{}-{}.js",
+            std::process::id(),
+            NEXT_SCRIPT.fetch_add(1, Ordering::Relaxed)
+        ));
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&path)?
+            .write_all(script.as_bytes())?;
+
+        Ok(path)
+    }
+
+    pub(crate) fn delete_deployment(&self, name: &str) -> Result<bool> {
+        self.delete_directory(self.get_deployment_dir(name))
+    }
+
+    pub(crate) fn list_deployments(&self) -> Result<Vec<String>> {
+        let deployments_dir = self.get_deployments_dir();
+
+        if !deployments_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut deployments = Vec::new();
+
+        for e in read_dir(deployments_dir)? {
+            let entry = e?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            if self.deployment_exists(&name) {
+                deployments.push(name);
+            }
+        }
+
+        deployments.sort();
+
+        Ok(deployments)
+    }
+
     pub fn delete_mongodb_version(&self, version: &str) -> Result<bool> {
-        self.delete_directory(self.get_version_dir(version))
+        self.delete_directory(self.get_version_dir(self.resolve_alias(version)))
     }
 
     pub fn write_mongodb_download(
@@ -261,22 +841,152 @@ impl Fs {
         filename: &str,
         dirname: &str,
         bytes: &[u8],
-        version: &str,
+        manifest: &Manifest,
     ) -> Result<()> {
+        // Installations of different IDs for the same release download to the same file, but
+        // everything else is only touched by installations of this ID, which hold its lock.
+        let _versions_lock = self.lock_versions_dir(LockMode::Shared)?;
+        let _download_lock = self.lock_download(filename)?;
         let bin_file = self.get_bin_file_rel(filename);
 
-        println!("writing {}...", bin_file.display());
+        self.events.emit(Event::Writing {
+            path: bin_file.clone(),
+        });
         self.write_file(&bin_file, bytes)?;
 
-        println!("decompressing...");
-        self.decompress_download(filename, dirname, version)?;
+        self.events.emit(Event::Decompressing {
+            path: bin_file.clone(),
+        });
+        self.decompress_download(filename, dirname, &manifest.id)?;
+        self.write_file(
+            self.get_version_dir(&manifest.id).join(MANIFEST_FILE),
+            toml::to_string(manifest)?.as_bytes(),
+        )?;
 
-        println!("cleaning up...");
+        self.events.emit(Event::CleaningUp);
         self.delete_file(&bin_file)?;
 
         Ok(())
     }
 
+    /// Reads the installation manifest of a version, if it has one.
+    pub(crate) fn read_manifest(&self, version: &str) -> Result<Option<Manifest>> {
+        let path = self
+            .get_version_dir(self.resolve_alias(version))
+            .join(MANIFEST_FILE);
+
+        match read_to_string(path) {
+            Ok(contents) => Ok(Some(toml::from_str(&contents)?)),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// When an installed version's directory was last modified, which is when it was installed
+    /// unless its files were changed afterwards.
+    pub(crate) fn version_modified_time(&self, version: &str) -> Result<SystemTime> {
+        Ok(metadata(self.get_version_dir(self.resolve_alias(version)))?.modified()?)
+    }
+
+    /// The disk space used by the files of an installed version in bytes.
+    pub(crate) fn version_size(&self, version: &str) -> Result<u64> {
+        dir_size(&self.get_version_dir(self.resolve_alias(version)))
+    }
+
+    /// The disk space used by the database files in a version's own data directory in bytes.
+    pub(crate) fn db_dir_size(&self, version: &str) -> Result<u64> {
+        let db_dir = self.get_file(self.get_db_file_rel(self.resolve_alias(version)));
+
+        if !db_dir.is_dir() {
+            return Ok(0);
+        }
+
+        dir_size(&db_dir)
+    }
+
+    #[inline]
+    pub(crate) fn get_shims_dir(&self) -> PathBuf {
+        self.get_file(DEFAULT_SHIMS_DIR)
+    }
+
+    /// The names of the executables in the `bin` directories of every installed version.
+    pub(crate) fn list_version_binaries(&self) -> Result<BTreeSet<String>> {
+        let mut binaries = BTreeSet::new();
+
+        for version in self.list_versions()? {
+            for binary in self.list_binaries(&version.to_string_lossy())? {
+                binaries.insert(binary.name);
+            }
+        }
+
+        Ok(binaries)
+    }
+
+    /// The executables in the `bin` directory of an installed version, sorted by name.
+    pub(crate) fn list_binaries(&self, version: &str) -> Result<Vec<BinaryInfo>> {
+        let bin_dir = self.get_version_dir(version).join("bin");
+        let mut binaries = Vec::new();
+
+        if !bin_dir.is_dir() {
+            return Ok(binaries);
+        }
+
+        for e in read_dir(bin_dir)? {
+            let entry = e?;
+            let metadata = entry.metadata()?;
+
+            if metadata.is_file() && metadata.permissions().mode() & 0o111 != 0 {
+                binaries.push(BinaryInfo {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    size: metadata.len(),
+                });
+            }
+        }
+
+        binaries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(binaries)
+    }
+
+    /// Replaces the contents of the shims directory with a symlink to `exe` for each binary.
+    pub(crate) fn write_shims<'a>(
+        &self,
+        exe: &Path,
+        binaries: impl IntoIterator<Item = &'a String>,
+    ) -> Result<()> {
+        let shims_dir = self.get_shims_dir();
+
+        if shims_dir.is_dir() {
+            remove_dir_all(&shims_dir)?;
+        }
+
+        create_dir_all(&shims_dir)?;
+
+        for binary in binaries {
+            symlink(exe, shims_dir.join(binary))?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn list_shims(&self) -> Result<Vec<String>> {
+        let shims_dir = self.get_shims_dir();
+
+        if !shims_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut shims = Vec::new();
+
+        for e in read_dir(shims_dir)? {
+            shims.push(e?.file_name().to_string_lossy().into_owned());
+        }
+
+        shims.sort();
+
+        Ok(shims)
+    }
+
     pub fn list_versions(&self) -> Result<Vec<OsString>> {
         self.create()?;
 
@@ -285,7 +995,9 @@ impl Fs {
         for e in read_dir(self.get_bin_dir())? {
             let entry = e?;
 
-            if entry.file_type()?.is_dir() {
+            if entry.file_type()?.is_dir()
+                && !self.is_partial_install(&entry.file_name().to_string_lossy())
+            {
                 versions.push(entry.file_name());
             }
         }
@@ -293,62 +1005,157 @@ impl Fs {
         Ok(versions)
     }
 
-    pub fn prune(&self) -> Result<()> {
-        let mut versions: HashMap<(u64, u64), _> = HashMap::new();
+    /// The directories monger stores versions and data in that it can't create files in, along
+    /// with the error from trying.
+    pub(crate) fn unwritable_dirs(&self) -> Vec<(PathBuf, std::io::Error)> {
+        let probe = format!(".monger-probe-{}", std::process::id());
+
+        vec![self.home_dir.clone(), self.get_bin_dir(), self.get_db_dir()]
+            .into_iter()
+            .filter_map(|dir| {
+                let result = create_dir_all(&dir)
+                    .and_then(|_| File::create(dir.join(&probe)))
+                    .and_then(|_| remove_file(dir.join(&probe)));
+
+                result.err().map(|e| (dir, e))
+            })
+            .collect()
+    }
+
+    /// Whether a directory in the versions directory was left behind by an installation that
+    /// failed before it finished. Archives are extracted into a temporary directory (named after
+    /// the archive, or `custom-download-<ID>` for custom URLs) that is renamed to the version's ID
+    /// once extracted, and the manifest is written after that.
+    fn is_partial_install(&self, name: &str) -> bool {
+        let version_dir = self.get_version_dir(name);
+
+        name.starts_with(CUSTOM_DOWNLOAD_PREFIX)
+            || (name.starts_with("mongodb-") && !version_dir.join(MANIFEST_FILE).is_file())
+            || !version_dir.join("bin").is_dir()
+    }
+
+    /// Finds what failed installations, snapshot restores, deleted versions and stopped
+    /// deployments left behind in monger's directories.
+    pub(crate) fn find_garbage(&self) -> Result<Vec<Garbage>> {
+        self.create()?;
+
+        // Installations in progress look the same as failed ones.
+        let _lock = self.lock_versions_dir(LockMode::Exclusive)?;
+        let mut garbage = Vec::new();
 
         for e in read_dir(self.get_bin_dir())? {
             let entry = e?;
+            let name = entry.file_name().to_string_lossy().into_owned();
 
-            // Skip unless the entry is a directory.
-            if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
-                continue;
+            if entry.file_type()?.is_dir() {
+                if self.is_partial_install(&name) {
+                    garbage.push(Garbage::new(GarbageKind::PartialInstall, entry.path())?);
+                }
+            } else if ARCHIVE_EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+                garbage.push(Garbage::new(GarbageKind::StrayArchive, entry.path())?);
             }
+        }
 
-            let version = match Version::parse(entry.file_name().to_string_lossy().as_ref()) {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
+        let db_dir = self.get_db_dir();
 
-            versions
-                .entry((version.major, version.minor))
-                .or_insert_with(BinaryHeap::new)
-                .push(version);
-        }
+        if db_dir.is_dir() {
+            let installed = self.list_versions()?;
 
-        for (_, vs) in versions {
-            self.prune_versions(vs)?;
-        }
+            for e in read_dir(&db_dir)? {
+                let entry = e?;
+                let name = entry.file_name().to_string_lossy().into_owned();
 
-        Ok(())
-    }
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
 
-    fn prune_versions(&self, mut versions: BinaryHeap<Version>) -> Result<()> {
-        let latest_stable = loop {
-            match versions.pop() {
-                Some(version) => {
-                    if version.build.is_empty() && version.pre.is_empty() {
-                        break version;
+                if !is_valid_name(&name) {
+                    if parse_restore_dir_name(&name).is_some_and(|pid| !process_is_running(pid)) {
+                        garbage.push(Garbage::new(GarbageKind::PartialRestore, entry.path())?);
                     }
+
+                    continue;
+                }
+
+                if installed.iter().any(|version| *version == *name) {
+                    continue;
+                }
+
+                // A version's own data directory is named after its ID, whereas named data
+                // directories record the version that last used them under a different name.
+                let recorded = self.read_data_metadata(&entry.path())?.version;
+                let is_version_dir = match recorded {
+                    Some(ref version) => *version == name,
+                    None => Version::parse(&name).is_ok(),
+                };
+
+                if is_version_dir && self.check_db_dir_unused(&entry.path()).is_ok() {
+                    garbage.push(Garbage::new(
+                        GarbageKind::OrphanedDataDir { version: name },
+                        entry.path(),
+                    )?);
                 }
-                None => return Ok(()),
             }
-        };
+        }
+
+        let deployments_dir = self.get_deployments_dir();
+
+        if deployments_dir.is_dir() {
+            for e in read_dir(deployments_dir)? {
+                let deployment_dir = e?.path();
+
+                if !deployment_dir.is_dir() {
+                    continue;
+                }
+
+                for e in read_dir(deployment_dir)? {
+                    let member_dir = e?.path();
+
+                    if !member_dir.is_dir() {
+                        continue;
+                    }
+
+                    for e in read_dir(member_dir)? {
+                        let path = e?.path();
+
+                        if path.extension().is_none_or(|ext| ext != "pid") {
+                            continue;
+                        }
+
+                        let pid = read_pid_file(&path);
 
-        for version in versions {
-            self.delete_mongodb_version(&format!("{}", version))?;
-            println!(
-                "Deleted {} (because {} is installed)",
-                version, latest_stable
-            );
+                        if !pid.is_some_and(process_is_running) {
+                            garbage.push(Garbage::new(GarbageKind::StalePidFile { pid }, path)?);
+                        }
+                    }
+                }
+            }
         }
 
-        Ok(())
+        Ok(garbage)
     }
 
-    pub fn exec_command(&self, binary_name: &str, args: Vec<OsString>, version: &str) -> Error {
-        let binary_path = match self.get_version_bin_dir(version) {
-            Ok(dir) => dir.join(binary_name),
-            Err(e) => return e.into(),
+    /// Finds a binary of a version, locking the version so it can't be deleted while the binary is
+    /// being started.
+    fn lock_version_binary(&self, binary_name: &str, version: &str) -> Result<(PathBuf, Lock)> {
+        let version = self.get_newest_matching_version(version)?;
+        let lock = self.lock_version(&version, LockMode::Shared)?;
+
+        Ok((self.get_version_bin_dir(&version)?.join(binary_name), lock))
+    }
+
+    /// Replaces the current process with a binary of a version. The version stays locked against
+    /// being deleted until the binary has been executed.
+    pub fn exec_command(
+        &self,
+        binary_name: &str,
+        args: Vec<OsString>,
+        version: &str,
+        stdout_to_stderr: bool,
+    ) -> Error {
+        let (binary_path, _lock) = match self.lock_version_binary(binary_name, version) {
+            Ok(locked) => locked,
+            Err(e) => return e,
         };
 
         let dir = match std::env::current_dir() {
@@ -356,7 +1163,12 @@ impl Fs {
             Err(e) => return e.into(),
         };
 
-        exec_command(binary_path.to_string_lossy().as_ref(), args, dir)
+        exec_command(
+            binary_path.to_string_lossy().as_ref(),
+            args,
+            dir,
+            stdout_to_stderr,
+        )
     }
 
     pub fn run_background_command(
@@ -365,7 +1177,7 @@ impl Fs {
         args: Vec<OsString>,
         version: &str,
     ) -> Result<Child> {
-        let binary_path = self.get_version_bin_dir(version)?.join(binary_name);
+        let (binary_path, _lock) = self.lock_version_binary(binary_name, version)?;
 
         run_background_command(
             binary_path.to_string_lossy().as_ref(),
@@ -380,27 +1192,56 @@ pub struct FsBuilder {
     home_dir: Option<String>,
     bin_dir: Option<String>,
     db_dir: Option<String>,
+    deployments_dir: Option<String>,
+    snapshots_dir: Option<String>,
+    aliases: BTreeMap<String, String>,
+    lock_timeout: Option<Duration>,
+    prereleases: bool,
 }
 
 impl FsBuilder {
-    #[allow(dead_code)]
     pub fn with_home_dir(&mut self, home_dir: &str) -> &mut Self {
         self.home_dir = Some(home_dir.into());
         self
     }
 
-    #[allow(dead_code)]
     pub fn with_bin_dir(&mut self, bin_dir: &str) -> &mut Self {
         self.bin_dir = Some(bin_dir.into());
         self
     }
 
-    #[allow(dead_code)]
     pub fn with_db_dir(&mut self, db_dir: &str) -> &mut Self {
         self.db_dir = Some(db_dir.into());
         self
     }
 
+    pub fn with_deployments_dir(&mut self, deployments_dir: &str) -> &mut Self {
+        self.deployments_dir = Some(deployments_dir.into());
+        self
+    }
+
+    pub fn with_snapshots_dir(&mut self, snapshots_dir: &str) -> &mut Self {
+        self.snapshots_dir = Some(snapshots_dir.into());
+        self
+    }
+
+    pub fn with_aliases(&mut self, aliases: BTreeMap<String, String>) -> &mut Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Sets how long to wait for a lock held by another monger process before giving up.
+    pub fn with_lock_timeout(&mut self, lock_timeout: Duration) -> &mut Self {
+        self.lock_timeout = Some(lock_timeout);
+        self
+    }
+
+    /// Lets major and minor versions and constraints resolve to installed pre-releases.
+    pub fn with_prereleases(&mut self, prereleases: bool) -> &mut Self {
+        self.prereleases = prereleases;
+        self
+    }
+
     pub fn build(self) -> Result<Fs> {
         match home_dir() {
             Some(mut home_dir) => {
@@ -412,13 +1253,333 @@ impl FsBuilder {
                 let db_dir =
                     Path::new(&self.db_dir.unwrap_or_else(|| DEFAULT_DB_DIR.into())).to_path_buf();
 
+                let deployments_dir = Path::new(
+                    &self
+                        .deployments_dir
+                        .unwrap_or_else(|| DEFAULT_DEPLOYMENTS_DIR.into()),
+                )
+                .to_path_buf();
+
+                let snapshots_dir = Path::new(
+                    &self
+                        .snapshots_dir
+                        .unwrap_or_else(|| DEFAULT_SNAPSHOTS_DIR.into()),
+                )
+                .to_path_buf();
+
                 Ok(Fs {
                     home_dir,
                     bin_dir,
                     db_dir,
+                    deployments_dir,
+                    snapshots_dir,
+                    aliases: self.aliases,
+                    lock_timeout: self.lock_timeout.unwrap_or(DEFAULT_LOCK_TIMEOUT),
+                    prereleases: self.prereleases,
+                    events: Default::default(),
                 })
             }
             None => Err(Error::UnknownHomeDirectory),
         }
     }
 }
+
+/// The total size of the files in a directory and its subdirectories, not following symlinks.
+pub(crate) fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+
+    for e in read_dir(dir)? {
+        let entry = e?;
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+
+    Ok(size)
+}
+
+/// Reads the PID from a PID file written by mongod or mongos.
+pub(crate) fn read_pid_file(path: &Path) -> Option<u32> {
+    read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Parses the name of a temporary directory used by `Fs::restore_snapshot`, which is of the form
+/// `.<NAME>.restore-<PID>` or `.<NAME>.old-<PID>`, into the PID of the monger process.
+fn parse_restore_dir_name(name: &str) -> Option<u32> {
+    let (prefix, pid) = name.strip_prefix('.')?.rsplit_once('-')?;
+
+    if prefix.ends_with(".restore") || prefix.ends_with(".old") {
+        pid.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains('/')
+}
+
+fn check_snapshot_name(name: &str) -> Result<()> {
+    if !is_valid_name(name) {
+        return Err(Error::InvalidSnapshotName { name: name.into() });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{create_dir_all, remove_dir_all, write},
+        process::Command,
+    };
+
+    use super::{check_snapshot_name, DataDir, DataMetadata, Fs};
+    use crate::{error::Error, gc::GarbageKind, manifest::Manifest, util::TempDir};
+
+    /// An `Fs` whose home directory is deleted once the returned guard is dropped.
+    fn temp_fs(name: &str) -> (TempDir, Fs) {
+        let home_dir = TempDir::new(name);
+
+        let mut builder = Fs::builder();
+        builder.with_home_dir(home_dir.to_str().unwrap());
+        let fs = builder.build().unwrap();
+
+        (home_dir, fs)
+    }
+
+    #[test]
+    fn newest_matching_version() {
+        let (_home_dir, fs) = temp_fs("newest-matching");
+
+        for id in &["4.4.1", "4.4.10", "4.4.11-rc0", "6.0.2", "6-debug", "4"] {
+            create_dir_all(fs.get_version_dir(id).join("bin")).unwrap();
+        }
+
+        let newest = |version: &str| fs.get_newest_matching_version(version).ok();
+
+        assert_eq!(Some("4.4.10".into()), newest("4.4"));
+        assert_eq!(Some("4.4.10".into()), newest(">=4.4, <5"));
+        assert_eq!(Some("6.0.2".into()), newest("6"));
+        assert_eq!(Some("6.0.2".into()), newest("^6"));
+        assert_eq!(Some("4.4.1".into()), newest("4.4.1"));
+
+        // IDs are matched exactly before being treated as constraints.
+        assert_eq!(Some("4".into()), newest("4"));
+        assert_eq!(Some("6-debug".into()), newest("6-debug"));
+
+        assert_eq!(None, newest("5"));
+        assert_eq!(None, newest("7-debug"));
+        assert_eq!(None, newest("~4.2"));
+    }
+
+    #[test]
+    fn data_dir_lifecycle() {
+        let (_home_dir, fs) = temp_fs("data-dirs");
+
+        let data_dir = fs.create_or_get_named_data_dir("orders-test").unwrap();
+        let metadata = DataMetadata {
+            version: Some("4.4.1".into()),
+            ..Default::default()
+        };
+        fs.write_data_metadata(&data_dir, &metadata).unwrap();
+
+        assert_eq!(
+            vec![DataDir {
+                name: "orders-test".into(),
+                version: Some("4.4.1".into()),
+            }],
+            fs.list_data_dirs().unwrap()
+        );
+
+        fs.clear_data_dir("orders-test").unwrap();
+        assert_eq!(None, fs.list_data_dirs().unwrap()[0].version);
+
+        fs.delete_data_dir("orders-test").unwrap();
+        assert!(fs.list_data_dirs().unwrap().is_empty());
+        assert!(matches!(
+            fs.delete_data_dir("orders-test"),
+            Err(Error::DataNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn snapshot_without_data() {
+        let (_home_dir, fs) = temp_fs("empty-snapshot");
+        create_dir_all(fs.get_version_dir("4.4.1").join("bin")).unwrap();
+        fs.create_or_get_named_data_dir("orders").unwrap();
+
+        assert!(matches!(
+            fs.save_snapshot("4.4.1", "before", false),
+            Err(Error::NoDatabaseFiles { .. })
+        ));
+        assert!(!fs.get_db_dir().join("4.4.1").exists());
+
+        assert!(matches!(
+            fs.save_snapshot("orders", "before", false),
+            Err(Error::NoDatabaseFiles { .. })
+        ));
+
+        write(fs.get_db_dir().join("orders").join("storage.bson"), b"").unwrap();
+        assert_eq!(
+            "orders",
+            fs.save_snapshot("orders", "before", false).unwrap().version
+        );
+    }
+
+    #[test]
+    fn aliases_to_system() {
+        let (_home_dir, mut fs) = temp_fs("system-alias");
+        fs.set_aliases(
+            vec![("host".to_string(), "system".to_string())]
+                .into_iter()
+                .collect(),
+        );
+
+        assert!(fs.is_system("system"));
+        assert!(fs.is_system("host"));
+        assert!(!fs.is_system("4.4"));
+        assert_eq!("system", fs.get_newest_matching_version("host").unwrap());
+    }
+
+    #[test]
+    fn find_garbage() {
+        let (_home_dir, fs) = temp_fs("garbage");
+        let versions = fs.get_bin_dir();
+        let db = fs.get_db_dir();
+        // No process has this PID, since it's above the kernel's limit.
+        let dead_pid = "4194305";
+
+        for dir in [
+            versions.join("4.4.1").join("bin"),
+            versions.join("custom-download-patched"),
+            versions.join("4.2.1"),
+            db.join("4.4.1"),
+            db.join("4.0.0"),
+            db.join(format!(".4.4.1.restore-{}", dead_pid)),
+            fs.get_deployment_dir("rs").join("rs0"),
+        ] {
+            create_dir_all(dir).unwrap();
+        }
+
+        write(versions.join("mongodb-linux-x86_64-4.2.1.tgz"), "").unwrap();
+        write(
+            fs.get_deployment_dir("rs").join("rs0").join("mongod.pid"),
+            dead_pid,
+        )
+        .unwrap();
+
+        let orders = fs.create_or_get_named_data_dir("orders").unwrap();
+        let metadata = DataMetadata {
+            version: Some("4.0.0".into()),
+            ..Default::default()
+        };
+        fs.write_data_metadata(&orders, &metadata).unwrap();
+
+        assert_eq!(
+            vec![std::ffi::OsString::from("4.4.1")],
+            fs.list_versions().unwrap()
+        );
+
+        let mut found: Vec<_> = fs
+            .find_garbage()
+            .unwrap()
+            .into_iter()
+            .map(|garbage| {
+                let name = garbage
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                (name, garbage.kind)
+            })
+            .collect();
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            vec![
+                (
+                    format!(".4.4.1.restore-{}", dead_pid),
+                    GarbageKind::PartialRestore
+                ),
+                (
+                    "4.0.0".into(),
+                    GarbageKind::OrphanedDataDir {
+                        version: "4.0.0".into()
+                    }
+                ),
+                ("4.2.1".into(), GarbageKind::PartialInstall),
+                (
+                    "custom-download-patched".into(),
+                    GarbageKind::PartialInstall
+                ),
+                (
+                    "mongod.pid".into(),
+                    GarbageKind::StalePidFile { pid: Some(4194305) }
+                ),
+                (
+                    "mongodb-linux-x86_64-4.2.1.tgz".into(),
+                    GarbageKind::StrayArchive
+                ),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn install_writes_manifest() {
+        let (_home_dir, fs) = temp_fs("install");
+
+        let source = fs.home_dir.join("mongodb-linux-x86_64-4.4.1");
+        create_dir_all(source.join("bin")).unwrap();
+        std::fs::write(source.join("bin").join("mongod"), "#!/bin/sh\n").unwrap();
+        let status = Command::new("tar")
+            .args(["czf", "archive.tgz", "mongodb-linux-x86_64-4.4.1"])
+            .current_dir(&fs.home_dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        remove_dir_all(&source).unwrap();
+
+        let archive = std::fs::read(fs.home_dir.join("archive.tgz")).unwrap();
+        let manifest = Manifest::new("4.4.1", "https://example.com/archive.tgz", &archive);
+        fs.write_mongodb_download(
+            "mongodb-linux-x86_64-4.4.1.tgz",
+            "mongodb-linux-x86_64-4.4.1",
+            &archive,
+            &manifest,
+        )
+        .unwrap();
+
+        assert!(fs.version_exists("4.4.1"));
+        assert_eq!(Some(manifest), fs.read_manifest("4.4.1").unwrap());
+        assert!(fs.version_size("4.4.1").unwrap() > 0);
+    }
+
+    #[test]
+    fn migrate_legacy_default_args() {
+        let (_home_dir, fs) = temp_fs("default-args");
+
+        fs.write_file("default-args", b"--quiet  --port 27018\n")
+            .unwrap();
+
+        let default_args = fs.read_default_args().unwrap();
+        assert_eq!(vec!["--quiet", "--port", "27018"], default_args.global);
+        assert!(!fs.get_file("default-args").exists());
+        assert_eq!(default_args, fs.read_default_args().unwrap());
+    }
+
+    #[test]
+    fn snapshot_names() {
+        assert!(check_snapshot_name("seeded").is_ok());
+        assert!(check_snapshot_name("seeded-2.0").is_ok());
+        assert!(check_snapshot_name("").is_err());
+        assert!(check_snapshot_name(".hidden").is_err());
+        assert!(check_snapshot_name("../escape").is_err());
+    }
+}
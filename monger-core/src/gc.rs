@@ -0,0 +1,184 @@
+use std::{
+    env,
+    fmt::{self, Display},
+    fs::{metadata, read_dir, remove_dir_all, remove_file},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{error::Result, lock::LockMode, process::process_is_running, Monger};
+
+/// What kind of leftover a `Garbage` is.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GarbageKind {
+    /// The database files of a version that is no longer installed.
+    OrphanedDataDir { version: String },
+
+    /// A downloaded archive that was never extracted because the installation failed.
+    StrayArchive,
+
+    /// A directory that a failed installation left half-extracted.
+    PartialInstall,
+
+    /// A directory that a failed snapshot restore left half-extracted.
+    PartialRestore,
+
+    /// A PID file of a mongod or mongos that is no longer running.
+    StalePidFile { pid: Option<u32> },
+
+    /// A log file of a temporary mongod whose monger process is no longer running.
+    StaleLogFile,
+}
+
+/// Something that monger left behind and no longer needs.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Garbage {
+    #[serde(flatten)]
+    pub kind: GarbageKind,
+
+    pub path: PathBuf,
+
+    /// The disk space used by the file or directory in bytes.
+    pub size: u64,
+}
+
+impl Garbage {
+    pub(crate) fn new(kind: GarbageKind, path: PathBuf) -> Result<Self> {
+        let size = if path.is_dir() {
+            crate::fs::dir_size(&path)?
+        } else {
+            metadata(&path)?.len()
+        };
+
+        Ok(Self { kind, path, size })
+    }
+}
+
+impl Display for Garbage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = self.path.display();
+
+        match self.kind {
+            GarbageKind::OrphanedDataDir { ref version } => write!(
+                f,
+                "{} holds the database files of {}, which is no longer installed",
+                path, version
+            ),
+            GarbageKind::StrayArchive => write!(
+                f,
+                "{} was downloaded by an installation that failed before extracting it",
+                path
+            ),
+            GarbageKind::PartialInstall => write!(
+                f,
+                "{} was left half-extracted by a failed installation",
+                path
+            ),
+            GarbageKind::PartialRestore => write!(
+                f,
+                "{} was left half-extracted by a failed snapshot restore",
+                path
+            ),
+            GarbageKind::StalePidFile { pid: Some(pid) } => write!(
+                f,
+                "{} refers to process {}, which is no longer running",
+                path, pid
+            ),
+            GarbageKind::StalePidFile { pid: None } => {
+                write!(f, "{} doesn't contain a valid PID", path)
+            }
+            GarbageKind::StaleLogFile => write!(
+                f,
+                "{} was written by a temporary mongod that is no longer running",
+                path
+            ),
+        }
+    }
+}
+
+/// Parses the name of a file written by `Monger::with_temporary_mongod`, which is of the form
+/// `monger-<PID>-<PORT>.<EXTENSION>`, into the PID of the monger process and the extension.
+fn parse_temp_file_name(name: &str) -> Option<(u32, &str)> {
+    let (stem, extension) = name.strip_prefix("monger-")?.rsplit_once('.')?;
+    let (pid, port) = stem.split_once('-')?;
+    port.parse::<u16>().ok()?;
+
+    Some((pid.parse().ok()?, extension))
+}
+
+/// Finds the files left in the temporary directory by temporary mongods whose monger process
+/// exited without cleaning up after them.
+fn find_temp_garbage(temp_dir: &Path) -> Result<Vec<Garbage>> {
+    let mut garbage = Vec::new();
+
+    for e in read_dir(temp_dir)? {
+        let entry = e?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        let kind = match parse_temp_file_name(&name) {
+            Some((pid, _)) if process_is_running(pid) => continue,
+            Some((_, "log")) => GarbageKind::StaleLogFile,
+            Some((_, "pid")) => GarbageKind::StalePidFile {
+                pid: crate::fs::read_pid_file(&entry.path()),
+            },
+            _ => continue,
+        };
+
+        if entry.file_type()?.is_file() {
+            garbage.push(Garbage::new(kind, entry.path())?);
+        }
+    }
+
+    Ok(garbage)
+}
+
+impl Monger {
+    /// Finds the files and directories that monger left behind and no longer needs: the database
+    /// files of deleted versions, the leftovers of failed installations and snapshot restores, and
+    /// stale PID files.
+    pub fn find_garbage(&self) -> Result<Vec<Garbage>> {
+        let mut garbage = self.fs.find_garbage()?;
+        garbage.extend(find_temp_garbage(&env::temp_dir())?);
+
+        Ok(garbage)
+    }
+
+    /// Deletes something found by `Monger::find_garbage`.
+    pub fn remove_garbage(&self, garbage: &Garbage) -> Result<()> {
+        let _lock = self.fs.lock_versions_dir(LockMode::Exclusive)?;
+
+        if let GarbageKind::OrphanedDataDir { .. } = garbage.kind {
+            self.fs.check_db_dir_unused(&garbage.path)?;
+        }
+
+        if garbage.path.is_dir() {
+            remove_dir_all(&garbage.path)?;
+        } else {
+            remove_file(&garbage.path)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_temp_file_name;
+
+    #[test]
+    fn temp_file_names() {
+        assert_eq!(
+            Some((1234, "pid")),
+            parse_temp_file_name("monger-1234-27017.pid")
+        );
+        assert_eq!(
+            Some((1234, "log")),
+            parse_temp_file_name("monger-1234-27017.log")
+        );
+        assert_eq!(None, parse_temp_file_name("monger-data-dirs-1234"));
+        assert_eq!(None, parse_temp_file_name("monger-1234-27017"));
+        assert_eq!(None, parse_temp_file_name("mongod-1234-27017.pid"));
+    }
+}
@@ -0,0 +1,242 @@
+use std::path::PathBuf;
+
+use semver::Version;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    error::{Error, Result},
+    manifest::{InstalledVersion, Manifest},
+    process::run_command_with_output,
+    spec::ReleaseKind,
+    Monger,
+};
+
+/// An executable in the `bin` directory of an installed version.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BinaryInfo {
+    pub name: String,
+
+    /// The size of the executable in bytes.
+    pub size: u64,
+}
+
+/// How a mongod was built, as reported by `mongod --version`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct BuildInfo {
+    pub version: Option<String>,
+    pub git_version: Option<String>,
+
+    /// The OpenSSL version the mongod was linked against, if any.
+    pub openssl_version: Option<String>,
+
+    /// The modules built into the mongod, e.g. "enterprise".
+    pub modules: Vec<String>,
+
+    pub allocator: Option<String>,
+
+    /// The platform the mongod was built for, e.g. "ubuntu1804".
+    pub distmod: Option<String>,
+
+    pub distarch: Option<String>,
+}
+
+/// Everything monger knows about an installed version.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct VersionInfo {
+    /// The installed version that the requested ID resolved to, e.g. "4.4.1" for "4.4".
+    pub id: String,
+
+    pub bin_dir: PathBuf,
+    pub binaries: Vec<BinaryInfo>,
+
+    /// How the version's mongod was built; unknown if it doesn't have one or it couldn't be run.
+    pub build_info: Option<BuildInfo>,
+
+    /// The disk space used by the version's files in bytes.
+    pub size: u64,
+
+    /// The disk space used by the database files in the version's own data directory in bytes.
+    pub data_size: u64,
+
+    pub manifest: Option<Manifest>,
+
+    /// What kind of release the version is; unknown if its server version is.
+    pub kind: Option<ReleaseKind>,
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.into())
+    }
+}
+
+/// Parses the output of `mongod --version`. Since 3.6, the build info is printed as JSON after a
+/// "Build Info:" line; older versions print a line for each field instead.
+fn parse_build_info(output: &str) -> BuildInfo {
+    if let Some(json) = output
+        .find("Build Info:")
+        .and_then(|start| serde_json::from_str::<Value>(&output[start + 11..]).ok())
+    {
+        let string = |value: &Value| value.as_str().and_then(non_empty);
+
+        return BuildInfo {
+            version: string(&json["version"]),
+            git_version: string(&json["gitVersion"]),
+            openssl_version: string(&json["openSSLVersion"]),
+            modules: json["modules"]
+                .as_array()
+                .map(|modules| modules.iter().filter_map(string).collect())
+                .unwrap_or_default(),
+            allocator: string(&json["allocator"]),
+            distmod: string(&json["environment"]["distmod"]),
+            distarch: string(&json["environment"]["distarch"]),
+        };
+    }
+
+    let mut info = BuildInfo::default();
+
+    for line in output.lines() {
+        let line = line.trim();
+
+        if let Some(version) = line.strip_prefix("db version v") {
+            info.version = non_empty(version);
+            continue;
+        }
+
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+
+        match key {
+            "git version" => info.git_version = non_empty(value),
+            "OpenSSL version" => info.openssl_version = non_empty(value),
+            "allocator" => info.allocator = non_empty(value),
+            "modules" if value != "none" => {
+                info.modules = value.split_whitespace().map(Into::into).collect()
+            }
+            "distmod" => info.distmod = non_empty(value),
+            "distarch" => info.distarch = non_empty(value),
+            _ => {}
+        }
+    }
+
+    info
+}
+
+impl Monger {
+    /// Describes an installed version: its binaries, how its mongod was built, its disk usage,
+    /// and its installation manifest.
+    pub fn version_info(&self, id: &str) -> Result<VersionInfo> {
+        let resolved = self
+            .fs
+            .get_newest_matching_version(id)
+            .ok()
+            .filter(|resolved| resolved != "system" && self.fs.version_exists(resolved))
+            .ok_or_else(|| Error::VersionNotFound { version: id.into() })?;
+
+        let bin_dir = self.fs.get_version_bin_dir(&resolved)?;
+        let binaries = self.fs.list_binaries(&resolved)?;
+
+        let build_info = if binaries.iter().any(|binary| binary.name == "mongod") {
+            run_command_with_output(
+                bin_dir.join("mongod").to_string_lossy().as_ref(),
+                vec!["--version"],
+                &bin_dir,
+            )
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| parse_build_info(&String::from_utf8_lossy(&output.stdout)))
+        } else {
+            None
+        };
+
+        let installed = InstalledVersion {
+            manifest: self.fs.read_manifest(&resolved)?,
+            id: resolved,
+            size: None,
+        };
+        let kind = installed.kind_or(
+            build_info
+                .as_ref()
+                .and_then(|build_info| Version::parse(build_info.version.as_deref()?).ok()),
+        );
+
+        Ok(VersionInfo {
+            size: self.fs.version_size(&installed.id)?,
+            data_size: self.fs.db_dir_size(&installed.id)?,
+            manifest: installed.manifest,
+            kind,
+            id: installed.id,
+            bin_dir,
+            binaries,
+            build_info,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_build_info, BuildInfo};
+
+    #[test]
+    fn json_build_info() {
+        let output = r#"db version v4.4.1
+Build Info: {
+    "version": "4.4.1",
+    "gitVersion": "ad91a93a5a31e175f5cbf8c69561e788bbc55ce1",
+    "openSSLVersion": "OpenSSL 1.1.1  11 Sep 2018",
+    "modules": ["enterprise"],
+    "allocator": "tcmalloc",
+    "environment": {
+        "distmod": "ubuntu1804",
+        "distarch": "x86_64",
+        "target_arch": "x86_64"
+    }
+}
+"#;
+
+        assert_eq!(
+            BuildInfo {
+                version: Some("4.4.1".into()),
+                git_version: Some("ad91a93a5a31e175f5cbf8c69561e788bbc55ce1".into()),
+                openssl_version: Some("OpenSSL 1.1.1  11 Sep 2018".into()),
+                modules: vec!["enterprise".into()],
+                allocator: Some("tcmalloc".into()),
+                distmod: Some("ubuntu1804".into()),
+                distarch: Some("x86_64".into()),
+            },
+            parse_build_info(output)
+        );
+    }
+
+    #[test]
+    fn legacy_build_info() {
+        let output = "db version v3.4.6
+git version: c55eb86ef46ee7aede3b1e2a5d184a7df4bfb5b5
+OpenSSL version: OpenSSL 1.0.2g  1 Mar 2016
+allocator: tcmalloc
+modules: none
+build environment:
+    distmod: ubuntu1604
+    distarch: x86_64
+    target_arch: x86_64
+";
+
+        assert_eq!(
+            BuildInfo {
+                version: Some("3.4.6".into()),
+                git_version: Some("c55eb86ef46ee7aede3b1e2a5d184a7df4bfb5b5".into()),
+                openssl_version: Some("OpenSSL 1.0.2g  1 Mar 2016".into()),
+                modules: Vec::new(),
+                allocator: Some("tcmalloc".into()),
+                distmod: Some("ubuntu1604".into()),
+                distarch: Some("x86_64".into()),
+            },
+            parse_build_info(output)
+        );
+    }
+}
@@ -1,56 +1,208 @@
 #[macro_use]
 mod util;
 
+mod aliases;
+mod batch;
 mod client;
+pub mod config;
+mod defaults;
+pub mod deployment;
+mod doctor;
+mod env;
 pub mod error;
+mod events;
+mod fcv;
 mod fs;
+mod gc;
+mod info;
+mod lock;
+mod manifest;
 pub mod os;
 pub mod process;
+mod project;
+mod prune;
+mod shims;
+mod spec;
 mod url;
-
-use std::{ffi::OsString, io::ErrorKind::NotFound, process::Child};
+mod which;
+
+use std::{
+    ffi::OsString,
+    io::ErrorKind::NotFound,
+    path::{Path, PathBuf},
+    process::Child,
+    time::Duration,
+};
 
 use lazy_static::lazy_static;
 use regex::Regex;
 use semver::Version;
 use soup::{NodeExt, QueryBuilderExt, Soup};
 
+pub use crate::{
+    batch::parse_version_list,
+    defaults::DefaultArgs,
+    doctor::{Diagnosis, Severity},
+    env::VersionEnv,
+    events::Event,
+    fs::{DataDir, Snapshot},
+    gc::{Garbage, GarbageKind},
+    info::{BinaryInfo, BuildInfo, VersionInfo},
+    manifest::{InstalledVersion, Manifest},
+    project::{PinnedVersion, VersionSource, VERSION_ENV_VAR},
+    prune::{ProtectedVersion, Protection, PrunePolicy, PruneReport, PrunedVersion},
+    spec::{ReleaseCatalog, ReleaseKind, VersionSpec},
+};
 use crate::{
     client::HttpClient,
+    config::{config_path, home_dir, Config},
     error::{Error, Result},
-    fs::Fs,
-    os::OperatingSystem,
+    events::Events,
+    fs::{DataMetadata, Fs},
+    lock::LockMode,
+    os::{Architecture, OperatingSystem},
     process::{exec_command, run_background_command},
-    util::{parse_major_minor_version, select_newer_version},
+    spec::compare_versions,
 };
 
 const MONGODB_VERSION_LIST_URL: &str = "https://dl.mongodb.org/dl/src";
+const MONGODB_RELEASE_FEED_URL: &str = "https://downloads.mongodb.org/full.json";
 
 lazy_static! {
     static ref MONGODB_SEMVER_REGEX: Regex =
-        Regex::new(r"src/mongodb-src-r(\d+\.\d+\.\d+)\.tar\.gz$").unwrap();
+        Regex::new(r"src/mongodb-src-r(\d+\.\d+\.\d+(?:-[0-9A-Za-z]+)?)\.tar\.gz$").unwrap();
+}
+
+/// A mongod that has passed the checks for starting it, returned by `Monger::prepare_mongod`.
+#[derive(Debug)]
+pub struct MongodCommand {
+    args: Vec<OsString>,
+    version: String,
+
+    /// The data directory managed by monger and the metadata to record in it once mongod has
+    /// started.
+    data_dir: Option<(PathBuf, DataMetadata)>,
 }
 
 #[derive(Debug)]
 pub struct Monger {
     client: HttpClient,
+    config: Config,
+    config_path: PathBuf,
+    events: Events,
     fs: Fs,
+    exec_stdout_to_stderr: bool,
+}
+
+/// A build of MongoDB that a version spec was resolved to.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Release {
+    /// The release, or for a nightly build, the release its branch will become (or for the
+    /// development branch, the newest release), which is used to choose the build for the OS.
+    version: Version,
+
+    /// The name of a nightly build in download URLs (e.g. "latest" or "v4.4-latest").
+    nightly: Option<String>,
+
+    /// The ID to install the build as unless another is given.
+    id: String,
+
+    /// The kind of release, if MongoDB's release feed was fetched to resolve it.
+    kind: Option<ReleaseKind>,
+}
+
+impl Release {
+    fn new(version: Version) -> Self {
+        Self {
+            id: version.to_string(),
+            version,
+            nightly: None,
+            kind: None,
+        }
+    }
+}
+
+/// The result of installing a version.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Installation {
+    pub id: String,
+
+    /// Whether the version was downloaded, as opposed to already being installed.
+    pub downloaded: bool,
 }
 
 impl Monger {
     pub fn new() -> Result<Self> {
+        let config_path = config_path()?;
+        let config = Config::load(&config_path)?;
+
+        Self::with_config(&home_dir()?, config_path, config)
+    }
+
+    /// Creates a monger that stores everything in `home_dir` and uses the given config.
+    pub(crate) fn with_config(
+        home_dir: &Path,
+        config_path: PathBuf,
+        config: Config,
+    ) -> Result<Self> {
+        let mut builder = Fs::builder();
+        builder.with_home_dir(&home_dir.to_string_lossy());
+
+        if let Some(ref bin_dir) = config.dirs.bin {
+            builder.with_bin_dir(bin_dir);
+        }
+
+        if let Some(ref db_dir) = config.dirs.db {
+            builder.with_db_dir(db_dir);
+        }
+
+        if let Some(ref deployments_dir) = config.dirs.deployments {
+            builder.with_deployments_dir(deployments_dir);
+        }
+
+        if let Some(ref snapshots_dir) = config.dirs.snapshots {
+            builder.with_snapshots_dir(snapshots_dir);
+        }
+
+        builder.with_aliases(config.aliases.clone());
+
+        if let Some(lock_timeout) = config.cache.lock_timeout {
+            builder.with_lock_timeout(Duration::from_secs(lock_timeout));
+        }
+
+        builder.with_prereleases(config.versions.prereleases);
+
         Ok(Self {
-            client: HttpClient::new()?,
-            fs: Fs::builder().build()?,
+            client: HttpClient::new(config.download.proxy.as_deref())?,
+            fs: builder.build()?,
+            config,
+            config_path,
+            events: Default::default(),
+            exec_stdout_to_stderr: false,
         })
     }
 
+    /// Sets the function that progress and warnings are reported to while monger works. By
+    /// default, they are discarded.
+    pub fn set_event_handler(&mut self, handler: impl Fn(&Event) + Send + Sync + 'static) {
+        self.events = Events::new(handler);
+        self.client.set_events(self.events.clone());
+        self.fs.set_events(self.events.clone());
+    }
+
+    /// Sets whether binaries that monger replaces itself with (e.g. the mongod started by
+    /// `Monger::start_mongod`) write their output to stderr instead of stdout, so that it doesn't
+    /// get mixed up with anything printed to stdout before.
+    pub fn set_exec_stdout_to_stderr(&mut self, stdout_to_stderr: bool) {
+        self.exec_stdout_to_stderr = stdout_to_stderr;
+    }
+
     pub fn clear_database_files(&self, version_str: &str) -> Result<bool> {
         self.fs.clear_db_dir(version_str)
     }
 
-    pub fn clear_default_args(&self) -> Result<bool> {
-        self.fs.clear_default_args()
+    pub fn clear_data_dir(&self, name: &str) -> Result<()> {
+        self.fs.clear_data_dir(name)
     }
 
     pub fn download_mongodb_version_from_url(
@@ -58,10 +210,12 @@ impl Monger {
         url: &str,
         id: &str,
         force: bool,
-    ) -> Result<()> {
+    ) -> Result<Installation> {
+        let _lock = self.fs.lock_version(id, LockMode::Exclusive)?;
+
         if self.fs.version_exists(id) {
             if force {
-                self.delete_mongodb_version(id)?;
+                self.fs.delete_mongodb_version(id)?;
             } else {
                 return Err(Error::ExistingId { id: id.into() });
             }
@@ -69,176 +223,361 @@ impl Monger {
 
         let dir = format!("custom-download-{}", id);
         let file = format!("{}.tgz", dir);
-        let data = self.client.download_url(&url)?;
+        let data = self.client.download_url(url)?;
+        let manifest = Manifest::new(id, url, &data);
 
         self.fs
-            .write_mongodb_download(&file, &dir, &data[..], &id)?;
+            .write_mongodb_download(&file, &dir, &data[..], &manifest)?;
 
-        Ok(())
+        Ok(Installation {
+            id: id.into(),
+            downloaded: true,
+        })
     }
 
     pub fn download_mongodb_version(
         &self,
         version_str: &str,
         force: bool,
+        include_prereleases: bool,
         os: Option<&str>,
         id: Option<&str>,
-    ) -> Result<()> {
-        let version = if version_str == "latest" {
-            self.find_latest_mongodb_version()?
-        } else if let Some((major, minor)) = parse_major_minor_version(version_str) {
-            self.find_latest_matching_version(major, minor)?
+    ) -> Result<Installation> {
+        let release = self
+            .resolve_releases(&[version_str], include_prereleases)?
+            .remove(0)?;
+
+        self.install_release(version_str, release, force, os, id)
+    }
+
+    /// Resolves version specs given to `monger get` (e.g. "4.4.1", ">=4.4, <5" or "latest") to
+    /// releases. The list of releases and MongoDB's release feed are each fetched at most once, and
+    /// only if one of the specs needs them. Pre-releases are only chosen for constraints if
+    /// `include_prereleases` is true or `versions.prereleases` is set.
+    pub(crate) fn resolve_releases(
+        &self,
+        version_strs: &[&str],
+        include_prereleases: bool,
+    ) -> Result<Vec<Result<Release>>> {
+        let prereleases = include_prereleases || self.config.versions.prereleases;
+        let specs: Vec<_> = version_strs
+            .iter()
+            .map(|version_str| VersionSpec::parse(version_str))
+            .collect();
+
+        let remote = if specs
+            .iter()
+            .flatten()
+            .any(|spec| !matches!(spec, VersionSpec::Exact(_) | VersionSpec::Nightly(Some(_))))
+        {
+            self.fetch_remote_versions()?
+        } else {
+            Vec::new()
+        };
+
+        let catalog = if specs.iter().flatten().any(VersionSpec::needs_catalog) {
+            self.fetch_release_catalog()
         } else {
-            crate::util::parse_version(version_str)?
+            ReleaseCatalog::default()
         };
 
-        let id = id
-            .map(ToString::to_string)
-            .unwrap_or_else(|| version.to_string());
+        Ok(version_strs
+            .iter()
+            .zip(specs)
+            .map(|(version_str, spec)| {
+                let release = match spec {
+                    Some(VersionSpec::Exact(version)) => Some(Release::new(version)),
+                    Some(VersionSpec::Nightly(branch)) => {
+                        let (version, nightly) = match branch {
+                            Some((major, minor)) => (
+                                version!(major, minor, 0),
+                                format!("v{}.{}-latest", major, minor),
+                            ),
+                            None => (
+                                VersionSpec::LatestDev
+                                    .select(&remote, &catalog, true)
+                                    .cloned()
+                                    .ok_or_else(|| Error::InvalidHtml {
+                                        url: MONGODB_VERSION_LIST_URL.to_string(),
+                                    })?,
+                                "latest".to_string(),
+                            ),
+                        };
+
+                        Some(Release {
+                            version,
+                            nightly: Some(nightly),
+                            id: version_str.to_string(),
+                            kind: None,
+                        })
+                    }
+                    Some(spec) => {
+                        spec.select(&remote, &catalog, prereleases)
+                            .map(|version| Release {
+                                kind: catalog.get(version),
+                                ..Release::new(version.clone())
+                            })
+                    }
+                    None => None,
+                };
+
+                release.ok_or_else(|| Error::VersionNotFound {
+                    version: version_str.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    /// Downloads and installs a release that `version_str` was resolved to.
+    pub(crate) fn install_release(
+        &self,
+        version_str: &str,
+        release: Release,
+        force: bool,
+        os: Option<&str>,
+        id: Option<&str>,
+    ) -> Result<Installation> {
+        let Release {
+            version,
+            nightly,
+            id: default_id,
+            kind,
+        } = release;
+        let id = id.map_or(default_id, ToString::to_string);
+
+        // Another process may be installing the same version, in which case this waits for it to
+        // finish and then finds the version installed.
+        let _lock = self.fs.lock_version(&id, LockMode::Exclusive)?;
 
         if self.fs.version_exists(&id) {
             if force {
-                self.delete_mongodb_version(&id)?;
+                self.fs.delete_mongodb_version(&id)?;
             } else {
-                return Ok(());
+                return Ok(Installation {
+                    id,
+                    downloaded: false,
+                });
             }
         }
 
-        let os = if let Some(os_name) = os {
-            OperatingSystem::from_name(os_name).unwrap()
-        } else {
-            OperatingSystem::get(&version)?
+        let mut os = match os.or(self.config.download.os.as_deref()) {
+            Some(os_name) => OperatingSystem::from_name(os_name).unwrap(),
+            None => OperatingSystem::get(&version)?,
         };
 
-        let url = os.download_url(&version);
+        if let Some(arch) = self
+            .config
+            .download
+            .arch
+            .as_deref()
+            .and_then(Architecture::from_name)
+        {
+            os = os.with_arch(arch);
+        }
+
+        let mut url = os.download_url(&version);
+
+        if let Some(ref build) = nightly {
+            url.set_build(build);
+        }
+
+        if let Some(ref mirror) = self.config.download.mirror {
+            url.set_mirror(mirror);
+        }
+
         let file = url.filename();
         let dir = url.dirname();
         let url: String = url.into();
-        let data = self.client.download_version(&url, &version_str)?;
+        let data = self.client.download_version(&url, version_str)?;
+        let mut manifest = Manifest::new(&id, &url, &data).with_target(&version, &os);
+        manifest.kind = kind;
+
+        if let Some(build) = nightly {
+            manifest = manifest.with_nightly(build);
+        }
 
         self.fs
-            .write_mongodb_download(&file, &dir, &data[..], &id)?;
+            .write_mongodb_download(&file, &dir, &data[..], &manifest)?;
 
-        Ok(())
-    }
+        self.check_cache_size()?;
 
-    fn find_latest_matching_version(&self, major: u64, minor: u64) -> Result<Version> {
-        let response = self.client.get(MONGODB_VERSION_LIST_URL)?;
-        let soup = Soup::from_reader(response)?;
+        Ok(Installation {
+            id,
+            downloaded: true,
+        })
+    }
 
-        let matches = soup
-            .tag("a")
-            .attr("href", MONGODB_SEMVER_REGEX.clone())
-            .find_all()
-            .map(|item| {
-                // We know the capture we're looking for will exist (and will be a valid semver
-                // string) due to Soup finding it as a match, so it's safe to unwrap
-                // here.
-                Version::parse(
-                    &*MONGODB_SEMVER_REGEX
-                        .captures(&item.text())
-                        .unwrap()
-                        .get(1)
-                        .unwrap()
-                        .as_str(),
-                )
-                .unwrap()
-            });
+    /// Suggests pruning old versions once more are installed than `cache.max_versions` allows.
+    fn check_cache_size(&self) -> Result<()> {
+        if let Some(max_versions) = self.config.cache.max_versions {
+            let installed = self.fs.list_versions()?.len();
 
-        for version in matches {
-            if major == version.major && minor == version.minor {
-                return Ok(version);
+            if installed > max_versions {
+                self.events.emit(Event::TooManyVersions {
+                    installed,
+                    max: max_versions,
+                });
             }
         }
 
-        Err(Error::VersionNotFound {
-            version: format!("{}.{}", major, minor),
-        })
+        Ok(())
+    }
+
+    /// Lists the released versions of MongoDB available to download with the kind of each, newest
+    /// first. If a constraint is given (e.g. `4.4` or `>=5, <7`), only the releases matching it are
+    /// listed.
+    pub fn list_remote_versions(
+        &self,
+        filter: Option<&str>,
+    ) -> Result<Vec<(Version, ReleaseKind)>> {
+        let constraint = match filter.map(|filter| (filter, VersionSpec::parse(filter))) {
+            Some((_, Some(spec @ VersionSpec::Constraint(_)))) => Some(spec),
+            Some((filter, _)) => {
+                return Err(Error::InvalidVersion {
+                    version: filter.into(),
+                })
+            }
+            None => None,
+        };
+
+        let mut versions: Vec<_> = self
+            .fetch_remote_versions()?
+            .into_iter()
+            .filter(|version| {
+                constraint
+                    .as_ref()
+                    .is_none_or(|spec| spec.matches(version, &ReleaseCatalog::default(), true))
+            })
+            .collect();
+
+        versions.sort_by(|a, b| compare_versions(b, a));
+        versions.dedup();
+
+        let catalog = self.fetch_release_catalog();
+
+        Ok(versions
+            .into_iter()
+            .map(|version| {
+                let kind = catalog.kind(&version);
+                (version, kind)
+            })
+            .collect())
     }
 
-    fn find_latest_mongodb_version(&self) -> Result<Version> {
+    /// Fetches the kinds of releases from MongoDB's release feed. Only releases from 5.0 on are
+    /// classified by it, so if it can't be fetched, they're classified by their version instead.
+    fn fetch_release_catalog(&self) -> ReleaseCatalog {
+        self.client
+            .get(MONGODB_RELEASE_FEED_URL)
+            .ok()
+            .filter(|response| response.status().is_success())
+            .and_then(|response| ReleaseCatalog::from_feed(response).ok())
+            .unwrap_or_default()
+    }
+
+    /// Scrapes the versions listed on the MongoDB download page, in the order they appear.
+    fn fetch_remote_versions(&self) -> Result<Vec<Version>> {
         let response = self.client.get(MONGODB_VERSION_LIST_URL)?;
         let soup = Soup::from_reader(response)?;
 
-        let mut newest_stable = None;
-        let mut newest_dev = None;
-
-        for version in soup
+        let versions = soup
             .tag("a")
             .attr("href", MONGODB_SEMVER_REGEX.clone())
             .find_all()
-            .map(|item| {
-                // We know the capture we're looking for will exist (and will be a valid semver
-                // string) due to Soup finding it as a match, so it's safe to unwrap
-                // here.
+            .filter_map(|item| {
+                // We know the capture we're looking for will exist due to Soup finding it as a
+                // match, so it's safe to unwrap here. Pre-release tags aren't checked by the
+                // regex, so they might not be valid semver.
                 Version::parse(
-                    &*MONGODB_SEMVER_REGEX
+                    MONGODB_SEMVER_REGEX
                         .captures(&item.text())
                         .unwrap()
                         .get(1)
                         .unwrap()
-                        .as_str()
-                        .to_string(),
+                        .as_str(),
                 )
-                .unwrap()
+                .ok()
             })
-        {
-            if version.minor % 2 == 0 {
-                newest_stable = Some(select_newer_version(newest_stable, version));
-            } else {
-                newest_dev = Some(version);
-            }
-
-            // Since there will only be one dev version in development at a given time, the newest
-            // stable version will never be older than one minor version less than the most recent
-            // dev version.
-            if let Some(ref stable_version) = newest_stable {
-                if let Some(ref dev_version) = newest_dev {
-                    if dev_version.major == stable_version.major
-                        && dev_version.minor == stable_version.minor + 1
-                    {
-                        return Ok(newest_stable.unwrap());
-                    }
-                }
-            }
-        }
+            .collect::<Vec<_>>();
 
-        if let Some(version) = newest_stable {
-            Ok(version)
-        } else {
-            Err(Error::InvalidHtml {
+        if versions.is_empty() {
+            return Err(Error::InvalidHtml {
                 url: MONGODB_VERSION_LIST_URL.to_string(),
-            })
+            });
         }
+
+        Ok(versions)
     }
 
-    pub fn delete_mongodb_version(&self, version: &str) -> Result<()> {
-        if self.fs.delete_mongodb_version(version)? {
-            println!("Deleted version {}", version);
+    /// Deletes an installed version, returning whether it existed. Aliases that referred to it are
+    /// kept, but reported with an `Event::AliasedVersionDeleted`.
+    pub fn delete_mongodb_version(&self, version: &str) -> Result<bool> {
+        let aliases = self
+            .fs
+            .get_newest_matching_version(version)
+            .map(|matched| self.aliases_of(&matched))
+            .unwrap_or_default();
+
+        let _lock = self
+            .fs
+            .lock_version(self.fs.resolve_alias(version), LockMode::Exclusive)?;
+
+        if !self.fs.delete_mongodb_version(version)? {
+            return Ok(false);
         }
 
-        Ok(())
+        for alias in aliases {
+            self.events.emit(Event::AliasedVersionDeleted {
+                alias: alias.into(),
+                version: version.into(),
+            });
+        }
+
+        Ok(true)
+    }
+
+    pub fn delete_data_dir(&self, name: &str) -> Result<()> {
+        self.fs.delete_data_dir(name)
+    }
+
+    pub fn delete_snapshot(&self, version: &str, name: &str) -> Result<bool> {
+        self.fs.delete_snapshot(version, name)
+    }
+
+    /// Gets a setting from the config file by its dotted key, e.g. `download.mirror`.
+    pub fn get_config(&self, key: &str) -> Result<Option<String>> {
+        self.config.get(key)
     }
 
-    pub fn get_default_args(&self) -> Result<Option<String>> {
-        self.fs.get_default_args()
+    /// Lists the settings in the config file and their values.
+    pub fn list_config(&self) -> Result<Vec<(String, String)>> {
+        self.config.list()
     }
 
     pub fn list_versions(&self) -> Result<Vec<OsString>> {
         self.fs.list_versions()
     }
 
-    pub fn prune(&self) -> Result<()> {
-        self.fs.prune()
+    pub fn list_data_dirs(&self) -> Result<Vec<DataDir>> {
+        self.fs.list_data_dirs()
     }
 
-    fn process_args(&self, args: Vec<OsString>, version: &str) -> Result<Vec<OsString>> {
+    pub fn list_snapshots(&self, version: Option<&str>) -> Result<Vec<Snapshot>> {
+        self.fs.list_snapshots(version)
+    }
+
+    fn process_args(
+        &self,
+        args: Vec<OsString>,
+        version: &str,
+        data: Option<&str>,
+    ) -> Result<Vec<OsString>> {
         let mut args = args.into_iter();
 
         let mut processed_args = Vec::new();
         let mut found_dbpath = false;
 
-        while let Some(arg) = args.next() {
+        for arg in args.by_ref() {
             if arg.as_os_str() == "--dbpath" {
                 processed_args.push(arg);
                 found_dbpath = true;
@@ -249,30 +588,132 @@ impl Monger {
         }
 
         if found_dbpath {
+            if data.is_some() {
+                return Err(Error::DataWithDbPath);
+            }
+
             processed_args.extend(args);
         } else {
+            let db_dir = match data {
+                Some(name) => self.fs.create_or_get_named_data_dir(name)?,
+                None => self.fs.create_or_get_db_dir(version)?,
+            };
+
             processed_args.push("--dbpath".into());
-            processed_args.push(self.fs.create_or_get_db_dir(version)?.into_os_string());
+            processed_args.push(db_dir.into_os_string());
         }
 
         Ok(processed_args)
     }
 
-    pub fn set_default_args(&self, default_args: &str) -> Result<()> {
-        self.fs.set_default_args(default_args)
+    /// Replaces the database files of a version with the contents of a snapshot. The existing
+    /// files are only removed once the snapshot has been fully extracted.
+    pub fn restore_snapshot(&self, version: &str, name: &str) -> Result<()> {
+        self.fs.restore_snapshot(version, name)
     }
 
-    pub fn start_mongod(&self, args: Vec<OsString>, version: &str, exec: bool) -> Result<Child> {
-        let mut processed_args = self.process_args(args, version)?;
+    /// Saves a compressed copy of the database files of a version. This fails if a mongod is
+    /// running against the files, since they may not be in a consistent state.
+    pub fn save_snapshot(&self, version: &str, name: &str, force: bool) -> Result<Snapshot> {
+        self.fs.save_snapshot(version, name, force)
+    }
 
-        if let Some(default_args) = self.fs.get_default_args()? {
-            processed_args.extend(default_args.split_whitespace().map(Into::into));
-        }
+    /// Sets a setting in the config file by its dotted key.
+    pub fn set_config(&mut self, key: &str, value: &str) -> Result<()> {
+        self.config.set(key, value)?;
+        self.config.save(&self.config_path)
+    }
+
+    /// Restores a setting in the config file to its default.
+    pub fn unset_config(&mut self, key: &str) -> Result<()> {
+        self.config.unset(key)?;
+        self.config.save(&self.config_path)
+    }
+
+    /// Starts a mongod of the given version. Unless a `--dbpath` is passed, the database files
+    /// are stored in the data directory named `data`, or in the version's own data directory if no
+    /// name is given.
+    ///
+    /// Starting a mongod with a data directory that was last used by a version it can't be
+    /// upgraded or downgraded from directly fails unless `force` is true.
+    pub fn start_mongod(
+        &self,
+        args: Vec<OsString>,
+        version: &str,
+        data: Option<&str>,
+        force: bool,
+        exec: bool,
+    ) -> Result<Child> {
+        let command = self.prepare_mongod(args, version, data, force)?;
 
         if exec {
-            Err(self.exec_command("mongod", processed_args, version))
+            Err(self.exec_mongod(command))
         } else {
-            self.run_background_command("mongod", processed_args, version)
+            self.spawn_mongod(command)
+        }
+    }
+
+    /// Runs the checks that `Monger::start_mongod` does before starting a mongod (that its data
+    /// directory can be used and that the binary exists) without starting it, so that anything
+    /// that should only happen once it's known to start can happen in between.
+    pub fn prepare_mongod(
+        &self,
+        args: Vec<OsString>,
+        version: &str,
+        data: Option<&str>,
+        force: bool,
+    ) -> Result<MongodCommand> {
+        let mut args = self.process_args(args, version, data)?;
+
+        let data_dir = match args
+            .iter()
+            .position(|arg| arg == "--dbpath")
+            .and_then(|i| args.get(i + 1))
+        {
+            Some(db_dir) => {
+                let db_dir = PathBuf::from(db_dir);
+                self.check_data_dir(&db_dir, version, force)?
+                    .map(|metadata| (db_dir, metadata))
+            }
+            None => None,
+        };
+
+        let default_args = self.resolve_default_args(version, &args)?;
+        args.extend(default_args.into_iter().map(Into::into));
+
+        self.binary_path(version, "mongod")?;
+
+        Ok(MongodCommand {
+            args,
+            version: version.into(),
+            data_dir,
+        })
+    }
+
+    /// Replaces the current process with a prepared mongod. This only returns if it fails.
+    pub fn exec_mongod(&self, command: MongodCommand) -> Error {
+        // Once the exec succeeds, there's nothing left to record the metadata afterwards, so it's
+        // recorded just before.
+        if let Err(e) = self.record_data_dir(&command) {
+            return e;
+        }
+
+        self.exec_command("mongod", command.args, &command.version)
+    }
+
+    /// Starts a prepared mongod in the background.
+    pub fn spawn_mongod(&self, command: MongodCommand) -> Result<Child> {
+        let child =
+            self.run_background_command("mongod", command.args.clone(), &command.version)?;
+        self.record_data_dir(&command)?;
+
+        Ok(child)
+    }
+
+    fn record_data_dir(&self, command: &MongodCommand) -> Result<()> {
+        match command.data_dir {
+            Some((ref data_dir, ref metadata)) => self.fs.write_data_metadata(data_dir, metadata),
+            None => Ok(()),
         }
     }
 
@@ -282,7 +723,7 @@ impl Monger {
         args: Vec<OsString>,
         version: &str,
     ) -> Result<Child> {
-        if version == "system" {
+        if self.fs.is_system(version) {
             return run_background_command(binary_name, args, std::env::current_dir()?);
         }
 
@@ -307,11 +748,11 @@ impl Monger {
             Err(e) => return e.into(),
         };
 
-        let error = if version == "system" {
-            exec_command(binary_name, args, dir)
+        let error = if self.fs.is_system(version) {
+            exec_command(binary_name, args, dir, self.exec_stdout_to_stderr)
         } else {
             self.fs
-                .exec_command(binary_name, args.into_iter().collect(), version)
+                .exec_command(binary_name, args, version, self.exec_stdout_to_stderr)
         };
 
         match error {
@@ -0,0 +1,165 @@
+use std::{
+    fs::{create_dir_all, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use fs2::{lock_contended_error, FileExt};
+
+use crate::{
+    error::{Error, Result},
+    events::{Event, Events},
+    process::process_is_running,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether other processes can hold a lock at the same time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LockMode {
+    /// For reading, e.g. running a version's binaries.
+    Shared,
+
+    /// For changing what's locked, e.g. installing or deleting a version.
+    Exclusive,
+}
+
+/// An advisory lock on a file, which is released when dropped (or when the process exits or execs
+/// another program). The file holds the PID of the last process to acquire the lock, so that
+/// processes waiting for it can say who they're waiting for.
+#[derive(Debug)]
+pub(crate) struct Lock {
+    file: File,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// The PID recorded in a lock file, if that process is still running.
+fn read_holder(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut contents).ok()?;
+
+    contents
+        .trim()
+        .parse()
+        .ok()
+        .filter(|pid| process_is_running(*pid))
+}
+
+/// Acquires a lock on a file, creating it if needed. If another process holds a conflicting lock,
+/// this waits for it to be released, emitting an event once, and fails after the timeout.
+pub(crate) fn acquire(
+    path: &Path,
+    name: &str,
+    mode: LockMode,
+    timeout: Duration,
+    events: &Events,
+) -> Result<Lock> {
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+
+    let start = Instant::now();
+    let mut waiting = false;
+
+    loop {
+        let result = match mode {
+            LockMode::Shared => FileExt::try_lock_shared(&file),
+            LockMode::Exclusive => FileExt::try_lock_exclusive(&file),
+        };
+
+        match result {
+            Ok(()) => break,
+            Err(ref e) if e.kind() == lock_contended_error().kind() => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let holder = read_holder(&mut file);
+
+        if start.elapsed() >= timeout {
+            return Err(Error::LockTimeout {
+                lock: name.into(),
+                holder: holder
+                    .map_or_else(|| "another process".into(), |pid| format!("PID {}", pid)),
+            });
+        }
+
+        if !waiting {
+            events.emit(Event::WaitingForLock {
+                lock: name.into(),
+                pid: holder,
+            });
+            waiting = true;
+        }
+
+        sleep(POLL_INTERVAL);
+    }
+
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    write!(file, "{}", std::process::id())?;
+
+    Ok(Lock { file })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::{acquire, LockMode};
+    use crate::{
+        error::Error,
+        events::{Event, Events},
+        util::TempDir,
+    };
+
+    #[test]
+    fn conflicting_locks() {
+        let dir = TempDir::new("lock");
+        let path = dir.join("test.lock");
+        let events = Events::default();
+        let timeout = Duration::from_millis(200);
+
+        let shared = acquire(&path, "test", LockMode::Shared, timeout, &events).unwrap();
+        let other = acquire(&path, "test", LockMode::Shared, timeout, &events).unwrap();
+        drop((shared, other));
+
+        let emitted = Arc::new(Mutex::new(Vec::new()));
+        let recorded = emitted.clone();
+        let events = Events::new(move |event| recorded.lock().unwrap().push(event.clone()));
+
+        let exclusive = acquire(&path, "test", LockMode::Exclusive, timeout, &events).unwrap();
+
+        assert!(matches!(
+            acquire(&path, "test", LockMode::Shared, timeout, &events),
+            Err(Error::LockTimeout { .. })
+        ));
+        assert_eq!(
+            vec![Event::WaitingForLock {
+                lock: "test".into(),
+                pid: Some(std::process::id()),
+            }],
+            *emitted.lock().unwrap()
+        );
+
+        drop(exclusive);
+        acquire(&path, "test", LockMode::Exclusive, timeout, &events).unwrap();
+    }
+}
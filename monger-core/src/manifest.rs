@@ -0,0 +1,251 @@
+use std::{cmp::Ordering, time::SystemTime};
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{error::Result, os::OperatingSystem, spec::compare_versions, Monger, ReleaseKind};
+
+const COMMUNITY_EDITION: &str = "community";
+
+/// A record of how an installed version was installed, stored in its directory at install time.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Manifest {
+    pub id: String,
+
+    /// The server version (e.g. "4.4.1"); unknown for nightly builds and downloads from a custom
+    /// URL.
+    pub version: Option<String>,
+
+    /// The nightly build that was downloaded instead of a release (e.g. "latest" or
+    /// "v4.4-latest").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nightly: Option<String>,
+
+    /// The URL the release was downloaded from.
+    pub url: String,
+
+    /// The OS the release was built for, as accepted by `--os`; unknown for downloads from a
+    /// custom URL.
+    pub os: Option<String>,
+
+    /// The architecture the release was built for; unknown for downloads from a custom URL.
+    pub arch: Option<String>,
+
+    /// Either "community" or "enterprise"; unknown for downloads from a custom URL.
+    pub edition: Option<String>,
+
+    /// The kind of release according to MongoDB's release feed, if it was fetched to install the
+    /// version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ReleaseKind>,
+
+    /// The SHA-256 checksum of the downloaded archive, in hex.
+    pub sha256: String,
+
+    /// When the version was installed, in RFC 3339 format.
+    pub installed_at: String,
+}
+
+impl Manifest {
+    pub(crate) fn new(id: &str, url: &str, archive: &[u8]) -> Self {
+        Self {
+            id: id.into(),
+            version: None,
+            nightly: None,
+            url: url.into(),
+            os: None,
+            arch: None,
+            edition: None,
+            kind: None,
+            sha256: Sha256::digest(archive)
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            installed_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        }
+    }
+
+    pub(crate) fn with_target(mut self, version: &Version, os: &OperatingSystem) -> Self {
+        self.version = Some(version.to_string());
+        self.os = Some(os.target().into());
+        self.arch = Some(os.architecture().name().into());
+        // Releases are always downloaded from the community edition's download server.
+        self.edition = Some(COMMUNITY_EDITION.into());
+        self
+    }
+
+    /// Records that a nightly build was installed, whose server version isn't known in advance.
+    pub(crate) fn with_nightly(mut self, build: String) -> Self {
+        self.version = None;
+        self.nightly = Some(build);
+        self
+    }
+}
+
+/// An installed version and what is known about it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstalledVersion {
+    pub id: String,
+
+    /// The installation manifest; missing for versions installed by older versions of monger.
+    pub manifest: Option<Manifest>,
+
+    /// The disk space used by the version's files in bytes; unknown unless sizes were asked for,
+    /// or if the files couldn't be measured.
+    pub size: Option<u64>,
+}
+
+impl InstalledVersion {
+    /// The server version, taken from the ID if it is one and from the manifest otherwise.
+    pub(crate) fn version(&self) -> Option<Version> {
+        Version::parse(&self.id).ok().or_else(|| {
+            self.manifest
+                .as_ref()
+                .and_then(|manifest| Version::parse(manifest.version.as_ref()?).ok())
+        })
+    }
+
+    /// The kind of release, as recorded in the manifest if it was known when the version was
+    /// installed, or classified by its server version otherwise.
+    pub(crate) fn kind(&self) -> Option<ReleaseKind> {
+        self.kind_or(None)
+    }
+
+    /// Like `InstalledVersion::kind`, but classifying by the given server version (e.g. the one
+    /// reported by the binary) if neither the ID nor the manifest has one.
+    pub(crate) fn kind_or(&self, server_version: Option<Version>) -> Option<ReleaseKind> {
+        if self.is_nightly() {
+            return Some(ReleaseKind::Prerelease);
+        }
+
+        self.manifest
+            .as_ref()
+            .and_then(|manifest| manifest.kind)
+            .or_else(|| {
+                self.version()
+                    .or(server_version)
+                    .map(|version| ReleaseKind::of(&version))
+            })
+    }
+
+    /// Whether this is a release candidate or other pre-release.
+    pub fn is_prerelease(&self) -> bool {
+        self.version()
+            .is_some_and(|version| !version.pre.is_empty())
+    }
+
+    /// Whether this is a nightly build rather than a release.
+    pub fn is_nightly(&self) -> bool {
+        self.manifest
+            .as_ref()
+            .is_some_and(|manifest| manifest.nightly.is_some())
+    }
+}
+
+/// Orders installed versions by server version, followed by those whose version is unknown in
+/// order of ID.
+fn compare_installed(a: &InstalledVersion, b: &InstalledVersion) -> Ordering {
+    match (a.version(), b.version()) {
+        (Some(x), Some(y)) => compare_versions(&x, &y).then_with(|| a.id.cmp(&b.id)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.id.cmp(&b.id),
+    }
+}
+
+impl Monger {
+    /// Lists the installed versions with their manifests, sorted by version. Measuring the disk
+    /// space used by each version means reading all of its files, so it's only done if `sizes` is
+    /// true.
+    pub fn list_installed(&self, sizes: bool) -> Result<Vec<InstalledVersion>> {
+        let mut installed = Vec::new();
+
+        for id in self.fs.list_versions()? {
+            let id = id.to_string_lossy().into_owned();
+
+            installed.push(InstalledVersion {
+                manifest: self.fs.read_manifest(&id)?,
+                size: if sizes {
+                    self.fs.version_size(&id).ok()
+                } else {
+                    None
+                },
+                id,
+            });
+        }
+
+        installed.sort_by(compare_installed);
+
+        Ok(installed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_installed, InstalledVersion, Manifest};
+    use crate::os::OperatingSystem;
+
+    fn installed(id: &str, version: Option<&str>) -> InstalledVersion {
+        let mut manifest = Manifest::new(id, "https://example.com/mongodb.tgz", b"");
+        manifest.version = version.map(Into::into);
+
+        InstalledVersion {
+            id: id.into(),
+            manifest: Some(manifest),
+            size: None,
+        }
+    }
+
+    #[test]
+    fn checksum() {
+        let manifest = Manifest::new("x", "https://example.com/mongodb.tgz", b"abc");
+
+        assert_eq!(
+            manifest.sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn edition() {
+        let custom = Manifest::new("x", "https://example.com/mongodb-enterprise.tgz", b"");
+        assert_eq!(None, custom.edition);
+
+        let release = Manifest::new("4.4.1", "https://example.com/mongodb.tgz", b"").with_target(
+            &version!(4, 4, 1),
+            &OperatingSystem::from_name("ubuntu1804").unwrap(),
+        );
+        assert_eq!(Some("community".to_string()), release.edition);
+    }
+
+    #[test]
+    fn semantic_order() {
+        let mut versions = [
+            installed("custom", None),
+            installed("10.0.1", None),
+            installed("4.4.10", None),
+            installed("my-build", Some("5.0.2")),
+            installed("4.4.9", None),
+            installed("4.4.9-rc0", None),
+            installed("4.4.9-rc10", None),
+            installed("4.4.9-rc2", None),
+        ];
+        versions.sort_by(compare_installed);
+
+        let ids: Vec<_> = versions.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "4.4.9-rc0",
+                "4.4.9-rc2",
+                "4.4.9-rc10",
+                "4.4.9",
+                "4.4.10",
+                "my-build",
+                "10.0.1",
+                "custom"
+            ]
+        );
+    }
+}
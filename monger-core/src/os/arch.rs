@@ -1,6 +1,5 @@
 #[derive(Clone, Copy, Debug)]
 pub enum Architecture {
-    #[allow(dead_code)]
     Arm,
 
     #[allow(non_camel_case_types)]
@@ -8,6 +7,14 @@ pub enum Architecture {
 }
 
 impl Architecture {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "arm64" => Some(Architecture::Arm),
+            "x86_64" => Some(Architecture::X86_64),
+            _ => None,
+        }
+    }
+
     pub fn name(self) -> &'static str {
         match self {
             Architecture::Arm => "arm64",
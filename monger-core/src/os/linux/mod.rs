@@ -51,15 +51,15 @@ impl LinuxType {
             .unwrap_or(LinuxType::Legacy))
     }
 
-    fn architecture(&self) -> Architecture {
+    pub(crate) fn architecture(&self) -> Architecture {
         if let LinuxType::Ubuntu1604(arch) = *self {
-            return arch;
+            arch
         } else {
-            return Architecture::X86_64;
+            Architecture::X86_64
         }
     }
 
-    fn name(&self) -> Option<&'static str> {
+    pub(crate) fn name(&self) -> Option<&'static str> {
         match *self {
             LinuxType::Amazon => Some("amazon"),
             LinuxType::Debian7 => Some("debian71"),
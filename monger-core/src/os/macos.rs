@@ -10,7 +10,7 @@ pub enum MacOsType {
 
 impl MacOsType {
     #[inline]
-    fn architecture(&self) -> Architecture {
+    pub(crate) fn architecture(&self) -> Architecture {
         Architecture::X86_64
     }
 
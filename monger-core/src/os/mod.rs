@@ -106,7 +106,37 @@ impl OperatingSystem {
     }
 
     pub fn from_name(name: &str) -> Option<Self> {
-        OS_MAP.get(name).map(Clone::clone)
+        OS_MAP.get(name).cloned()
+    }
+
+    /// The name of the OS as accepted by `from_name` (and `--os`).
+    pub fn target(&self) -> &'static str {
+        match *self {
+            OperatingSystem::Linux(LinuxType::Ubuntu1604(Architecture::Arm)) => "ubuntu1604-arm",
+            OperatingSystem::Linux(ref os_type) => os_type.name().unwrap_or("legacy"),
+            OperatingSystem::MacOs(MacOsType::Ssl) => "osx",
+            OperatingSystem::MacOs(MacOsType::NonSsl) => "osx-nossl",
+            OperatingSystem::Windows(_) => "windows",
+        }
+    }
+
+    pub fn architecture(&self) -> Architecture {
+        match *self {
+            OperatingSystem::Linux(ref os_type) => os_type.architecture(),
+            OperatingSystem::MacOs(ref os_type) => os_type.architecture(),
+            OperatingSystem::Windows(ref os_type) => os_type.architecture(),
+        }
+    }
+
+    /// Downloads releases built for the given architecture instead, for the OSes that monger
+    /// knows of builds for more than one architecture.
+    pub fn with_arch(self, arch: Architecture) -> Self {
+        match self {
+            OperatingSystem::Linux(LinuxType::Ubuntu1604(_)) => {
+                OperatingSystem::Linux(LinuxType::Ubuntu1604(arch))
+            }
+            os => os,
+        }
     }
 
     pub fn download_url(&self, version: &Version) -> Url {
@@ -258,6 +288,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn configured_arch_linux_url() {
+        matches_url(
+            "https://fastdl.mongodb.org/linux/mongodb-linux-arm64-ubuntu1604-3.4.6.tgz",
+            OperatingSystem::Linux(LinuxType::Ubuntu1604(Architecture::X86_64))
+                .with_arch(Architecture::Arm),
+            version!(3, 4, 6),
+        );
+    }
+
+    #[test]
+    fn mirror_url() {
+        let mut url =
+            OperatingSystem::Linux(LinuxType::Ubuntu1804).download_url(&version!(4, 0, 1));
+        url.set_mirror("https://mirror.example.com/mongodb/");
+
+        assert_eq!(
+            "https://mirror.example.com/mongodb/linux/mongodb-linux-x86_64-ubuntu1804-4.0.1.tgz",
+            String::from(url)
+        );
+    }
+
+    #[test]
+    fn nightly_url() {
+        let mut url =
+            OperatingSystem::Linux(LinuxType::Ubuntu1804).download_url(&version!(4, 4, 0));
+        url.set_build("v4.4-latest");
+
+        assert_eq!(
+            "https://fastdl.mongodb.org/linux/mongodb-linux-x86_64-ubuntu1804-v4.4-latest.tgz",
+            String::from(url)
+        );
+    }
+
+    #[test]
+    fn prerelease_url() {
+        matches_url(
+            "https://fastdl.mongodb.org/linux/mongodb-linux-x86_64-ubuntu1804-4.4.0-rc13.tgz",
+            OperatingSystem::Linux(LinuxType::Ubuntu1804),
+            Version::parse("4.4.0-rc13").unwrap(),
+        );
+    }
+
     // MacOS URLs
     //
 
@@ -323,4 +396,11 @@ mod tests {
             version!(3, 4, 6),
         );
     }
+
+    #[test]
+    fn targets_round_trip() {
+        for (name, os) in super::OS_MAP.iter() {
+            assert_eq!(*name, os.target());
+        }
+    }
 }
@@ -11,7 +11,7 @@ pub enum WindowsType {
 
 impl WindowsType {
     #[inline]
-    fn architecture(&self) -> Architecture {
+    pub(crate) fn architecture(&self) -> Architecture {
         Architecture::X86_64
     }
 
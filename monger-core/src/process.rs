@@ -1,15 +1,39 @@
 use std::{
+    convert::TryFrom,
     ffi::{OsStr, OsString},
-    os::unix::process::CommandExt,
-    path::Path,
-    process::{Child, Command, Stdio},
+    fs::{read_dir, read_link},
+    io,
+    os::{fd::AsFd, unix::process::CommandExt},
+    path::{Path, PathBuf},
+    process::{Child, Command, Output, Stdio},
+    thread::sleep,
+    time::{Duration, Instant},
 };
 
 use crate::error::{Error, Result};
 
-pub(crate) fn exec_command(cmd: &str, args: Vec<OsString>, dir: impl AsRef<Path>) -> Error {
+/// Replaces the current process with the command. If `stdout_to_stderr` is true, the command's
+/// stdout is redirected to stderr.
+pub(crate) fn exec_command(
+    cmd: &str,
+    args: Vec<OsString>,
+    dir: impl AsRef<Path>,
+    stdout_to_stderr: bool,
+) -> Error {
+    let mut command = Command::new(cmd);
+    command.current_dir(dir).args(args);
+
+    if stdout_to_stderr {
+        match io::stderr().as_fd().try_clone_to_owned() {
+            Ok(stderr) => {
+                command.stdout(stderr);
+            }
+            Err(e) => return e.into(),
+        }
+    }
+
     Error::Io {
-        inner: Command::new(cmd).current_dir(dir).args(args).exec(),
+        inner: command.exec(),
     }
 }
 
@@ -44,3 +68,67 @@ pub(crate) fn run_background_command(
 
     Ok(child)
 }
+
+pub(crate) fn run_command_with_output(
+    cmd: &str,
+    args: Vec<impl AsRef<OsStr>>,
+    dir: impl AsRef<Path>,
+) -> Result<Output> {
+    let output = Command::new(cmd)
+        .current_dir(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()?;
+
+    Ok(output)
+}
+
+/// Whether a process with the given PID exists. Processes of other users can't be sent signals,
+/// but they still exist, so they count as running.
+pub(crate) fn process_is_running(pid: u32) -> bool {
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => pid,
+        _ => return false,
+    };
+
+    // Signal 0 only checks whether the signal could be sent.
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+
+    io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// The executables of the running processes that can be inspected by the current user. This is
+/// always empty on platforms without `/proc`.
+pub(crate) fn running_executables() -> Vec<PathBuf> {
+    let entries = match read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| read_link(entry.ok()?.path().join("exe")).ok())
+        .collect()
+}
+
+/// Sends SIGTERM to the process with the given PID and waits for it to exit.
+pub(crate) fn stop_process(pid: u32, timeout: Duration) -> Result<()> {
+    if !process_is_running(pid) {
+        return Ok(());
+    }
+
+    run_foreground_command("kill", vec![pid.to_string()], std::env::temp_dir())?;
+
+    let start = Instant::now();
+
+    while process_is_running(pid) {
+        if start.elapsed() > timeout {
+            return Err(Error::ProcessTimeout { pid });
+        }
+
+        sleep(Duration::from_millis(100));
+    }
+
+    Ok(())
+}
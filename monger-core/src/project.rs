@@ -0,0 +1,252 @@
+use std::{
+    env,
+    fmt::{self, Display},
+    fs::read_to_string,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    error::{Error, Result},
+    Monger,
+};
+
+/// The file that pins the MongoDB version used by a project.
+const VERSION_FILE: &str = ".monger-version";
+
+/// A project config file that can pin the MongoDB version in a `[tool.monger]` table instead.
+const PYPROJECT_FILE: &str = "pyproject.toml";
+
+/// The environment variable that overrides the version used when none is given.
+pub const VERSION_ENV_VAR: &str = "MONGER_VERSION";
+
+/// Where the version used when none is given was chosen from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VersionSource {
+    /// The `MONGER_VERSION` environment variable.
+    Environment,
+
+    /// A `.monger-version` or `pyproject.toml` file in the current project.
+    File(PathBuf),
+
+    /// The `versions.default` setting in the config file.
+    Default,
+}
+
+impl Display for VersionSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VersionSource::Environment => write!(f, "${}", VERSION_ENV_VAR),
+            VersionSource::File(path) => write!(f, "{}", path.display()),
+            VersionSource::Default => write!(f, "the default version"),
+        }
+    }
+}
+
+/// The version used when none is given, e.g. "4.4" or "4.4.1".
+#[derive(Clone, Debug, PartialEq)]
+pub struct PinnedVersion {
+    pub version: String,
+    pub source: VersionSource,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PyProject {
+    #[serde(default)]
+    tool: Tools,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Tools {
+    monger: Option<MongerTool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MongerTool {
+    version: Option<String>,
+}
+
+fn invalid_file(path: &Path, reason: impl Into<String>) -> Error {
+    Error::InvalidVersionFile {
+        path: path.display().to_string(),
+        reason: reason.into(),
+    }
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<String>> {
+    match read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads the version from a `.monger-version` file, which contains it on the first line that isn't
+/// blank or a `#` comment.
+fn read_version_file(path: &Path) -> Result<Option<String>> {
+    let contents = match read_if_exists(path)? {
+        Some(contents) => contents,
+        None => return Ok(None),
+    };
+
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| Some(line.to_string()))
+        .ok_or_else(|| invalid_file(path, "it doesn't contain a version"))
+}
+
+/// Reads the version from the `[tool.monger]` table of a `pyproject.toml`, if it has one.
+fn read_pyproject(path: &Path) -> Result<Option<String>> {
+    let contents = match read_if_exists(path)? {
+        Some(contents) => contents,
+        None => return Ok(None),
+    };
+
+    let pyproject: PyProject =
+        toml::from_str(&contents).map_err(|e| invalid_file(path, e.to_string()))?;
+
+    match pyproject.tool.monger {
+        Some(MongerTool {
+            version: Some(version),
+        }) => Ok(Some(version)),
+        Some(MongerTool { version: None }) => {
+            Err(invalid_file(path, "[tool.monger] doesn't set a version"))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Finds the version pinned by the nearest project, looking in `dir` and then each of its parents,
+/// up to and including `root` if one is given. In each directory, a `.monger-version` file takes
+/// precedence over a `pyproject.toml`.
+fn find_pinned_version(dir: &Path, root: Option<&Path>) -> Result<Option<PinnedVersion>> {
+    for dir in dir.ancestors() {
+        let version_file = dir.join(VERSION_FILE);
+
+        if let Some(version) = read_version_file(&version_file)? {
+            return Ok(Some(PinnedVersion {
+                version,
+                source: VersionSource::File(version_file),
+            }));
+        }
+
+        let pyproject_file = dir.join(PYPROJECT_FILE);
+
+        if let Some(version) = read_pyproject(&pyproject_file)? {
+            return Ok(Some(PinnedVersion {
+                version,
+                source: VersionSource::File(pyproject_file),
+            }));
+        }
+
+        if Some(dir) == root {
+            break;
+        }
+    }
+
+    Ok(None)
+}
+
+impl Monger {
+    /// Determines the version used when none is given: the one in `$MONGER_VERSION` if it's set,
+    /// or else the one pinned by the project in the current directory, or else the default version
+    /// from the config file.
+    pub fn pinned_version(&self) -> Result<Option<PinnedVersion>> {
+        if let Some(version) = env::var(VERSION_ENV_VAR).ok().filter(|v| !v.is_empty()) {
+            return Ok(Some(PinnedVersion {
+                version,
+                source: VersionSource::Environment,
+            }));
+        }
+
+        if let Some(pinned) = find_pinned_version(&env::current_dir()?, None)? {
+            return Ok(Some(pinned));
+        }
+
+        Ok(self
+            .config
+            .versions
+            .default
+            .clone()
+            .map(|version| PinnedVersion {
+                version,
+                source: VersionSource::Default,
+            }))
+    }
+
+    /// Determines which version to use, falling back to the pinned version if none is given
+    /// explicitly.
+    pub fn resolve_version(&self, id: Option<&str>) -> Result<String> {
+        if let Some(id) = id {
+            return Ok(id.into());
+        }
+
+        self.pinned_version()?
+            .map(|pinned| pinned.version)
+            .ok_or(Error::NoPinnedVersion)
+    }
+
+    /// The installed version that a pinned version refers to, e.g. the newest installed 4.4.x
+    /// release for "4.4", if there is one.
+    pub fn installed_version(&self, version: &str) -> Option<String> {
+        self.fs.get_newest_matching_version(version).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, write};
+
+    use super::{find_pinned_version, PinnedVersion, VersionSource, PYPROJECT_FILE, VERSION_FILE};
+    use crate::util::TempDir;
+
+    #[test]
+    fn find_nearest_pin() {
+        let root = TempDir::new("pins");
+
+        let project = root.join("project");
+        let nested = project.join("src").join("db");
+        create_dir_all(&nested).unwrap();
+
+        write(
+            root.join(PYPROJECT_FILE),
+            "[project]\nname = \"app\"\n\n[tool.monger]\nversion = \"5.0\"\n",
+        )
+        .unwrap();
+        write(project.join(VERSION_FILE), "# pinned for CI\n\n4.4\n").unwrap();
+
+        assert_eq!(
+            Some(PinnedVersion {
+                version: "4.4".into(),
+                source: VersionSource::File(project.join(VERSION_FILE)),
+            }),
+            find_pinned_version(&nested, Some(&root)).unwrap()
+        );
+        assert_eq!(
+            Some("5.0".to_string()),
+            find_pinned_version(&root, Some(&root))
+                .unwrap()
+                .map(|pinned| pinned.version)
+        );
+
+        write(project.join(VERSION_FILE), "\n").unwrap();
+        assert!(find_pinned_version(&nested, Some(&root)).is_err());
+    }
+
+    #[test]
+    fn pyproject_without_monger_table() {
+        let root = TempDir::new("pyproject");
+
+        write(
+            root.join(PYPROJECT_FILE),
+            "[tool.black]\nline-length = 100\n",
+        )
+        .unwrap();
+
+        assert_eq!(None, find_pinned_version(&root, Some(&root)).unwrap());
+    }
+}
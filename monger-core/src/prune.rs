@@ -0,0 +1,543 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BTreeMap,
+    fmt::{self, Display},
+    time::{Duration, SystemTime},
+};
+
+use semver::Version;
+use serde::Serialize;
+
+use crate::{
+    error::Result, lock::LockMode, manifest::InstalledVersion, process::running_executables,
+    spec::compare_versions, Monger, ReleaseKind, VersionSource,
+};
+
+/// Which installed versions `Monger::prune` deletes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrunePolicy {
+    /// The number of the newest releases of each major and minor version to keep; at least one is
+    /// always kept.
+    pub keep: usize,
+
+    /// Only delete versions that were installed longer ago than this.
+    pub older_than: Option<Duration>,
+
+    /// Rank release candidates along with stable releases, so that they're kept or deleted like
+    /// any other release. By default, only stable releases are kept, and release candidates newer
+    /// than the newest stable release are left alone.
+    pub include_prereleases: bool,
+
+    /// Only report what would be deleted.
+    pub dry_run: bool,
+}
+
+impl Default for PrunePolicy {
+    fn default() -> Self {
+        Self {
+            keep: 1,
+            older_than: None,
+            include_prereleases: false,
+            dry_run: false,
+        }
+    }
+}
+
+/// An installed version deleted (or that would be deleted) by `Monger::prune`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PrunedVersion {
+    pub version: String,
+
+    /// The newest installed release of the same major and minor version, which is kept; `None`
+    /// for versions whose server version is unknown (i.e. those downloaded from a custom URL),
+    /// which are only deleted for being older than `PrunePolicy::older_than`.
+    pub newer_version: Option<String>,
+
+    /// The disk space used by the version's files in bytes.
+    pub size: u64,
+}
+
+/// Why a version that would otherwise be pruned is kept.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Protection {
+    Aliased { aliases: Vec<String> },
+    Default,
+    Pinned { source: String },
+    Running,
+}
+
+impl Display for Protection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Protection::Aliased { aliases } => write!(f, "aliased as {}", aliases.join(", ")),
+            Protection::Default => write!(f, "it's the default version"),
+            Protection::Pinned { source } => write!(f, "pinned by {}", source),
+            Protection::Running => write!(f, "it's running"),
+        }
+    }
+}
+
+/// An installed version that `Monger::prune` kept even though the policy allowed deleting it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProtectedVersion {
+    pub version: String,
+
+    #[serde(flatten)]
+    pub protection: Protection,
+}
+
+/// What `Monger::prune` deleted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PruneReport {
+    pub pruned: Vec<PrunedVersion>,
+    pub protected: Vec<ProtectedVersion>,
+
+    /// The disk space freed (or that would be freed) in bytes.
+    pub reclaimed: u64,
+}
+
+/// An installed release given by its ID, server version, and kind of release.
+type InstalledRelease<'a> = (&'a str, Version, ReleaseKind);
+
+/// How strongly a release is preferred as one to keep for its major and minor version: stable and
+/// LTS releases over rapid ones, over development ones. Pre-releases rank like the release they
+/// precede.
+fn rank(version: &Version, kind: ReleaseKind) -> u8 {
+    match kind {
+        ReleaseKind::Stable | ReleaseKind::Lts => 2,
+        ReleaseKind::Rapid => 1,
+        ReleaseKind::Development => 0,
+        ReleaseKind::Prerelease => {
+            let release = Version {
+                pre: Vec::new(),
+                ..version.clone()
+            };
+
+            rank(&release, ReleaseKind::of(&release))
+        }
+    }
+}
+
+/// Chooses which versions a policy allows deleting, pairing each with the newest kept release of
+/// the same major and minor version. Releases are ranked by kind before version, so that e.g. a
+/// development release isn't kept in place of an LTS one; those that rank lower than the newest
+/// kept release but are newer than it are left alone.
+fn select_prunable<'a>(
+    versions: &[InstalledRelease<'a>],
+    keep: usize,
+    include_prereleases: bool,
+) -> Vec<(&'a str, &'a str)> {
+    let mut releases: BTreeMap<(u64, u64), Vec<&InstalledRelease<'a>>> = BTreeMap::new();
+
+    for version in versions {
+        releases
+            .entry((version.1.major, version.1.minor))
+            .or_default()
+            .push(version);
+    }
+
+    let mut prunable = Vec::new();
+
+    for (_, mut release) in releases {
+        release.sort_by(|a, b| compare_versions(&b.1, &a.1));
+
+        let mut ranked: Vec<_> = release
+            .iter()
+            .filter(|(_, version, kind)| {
+                include_prereleases
+                    || (*kind != ReleaseKind::Prerelease && version.build.is_empty())
+            })
+            .collect();
+        ranked.sort_by_key(|(_, version, kind)| Reverse(rank(version, *kind)));
+
+        let newest = match ranked.first() {
+            Some((id, version, _)) => (*id, version),
+            None => continue,
+        };
+
+        let kept: Vec<_> = ranked
+            .iter()
+            .take(keep.max(1))
+            .map(|(id, _, _)| *id)
+            .collect();
+
+        for (id, version, _) in release.iter().rev() {
+            if kept.contains(id) || compare_versions(version, newest.1) == Ordering::Greater {
+                continue;
+            }
+
+            prunable.push((*id, newest.0));
+        }
+    }
+
+    prunable
+}
+
+impl Monger {
+    /// Deletes old releases of each major and minor version according to a policy. Versions that
+    /// are aliased, set as the default, pinned, or running are never deleted. Versions whose server
+    /// version is unknown (i.e. those downloaded from a custom URL) can't be ranked, so they're
+    /// only deleted if the policy has an `older_than` age that they're older than.
+    pub fn prune(&self, policy: &PrunePolicy) -> Result<PruneReport> {
+        let installed = self.list_installed(false)?;
+        let versions: Vec<_> = installed
+            .iter()
+            .filter_map(|installed| {
+                Some((
+                    installed.id.as_str(),
+                    installed.version()?,
+                    installed.kind()?,
+                ))
+            })
+            .collect();
+
+        let cutoff = policy
+            .older_than
+            .and_then(|age| SystemTime::now().checked_sub(age));
+
+        let mut candidates: Vec<_> =
+            select_prunable(&versions, policy.keep, policy.include_prereleases)
+                .into_iter()
+                .map(|(id, newer_version)| (id, Some(newer_version)))
+                .collect();
+
+        if cutoff.is_some() {
+            candidates.extend(
+                installed
+                    .iter()
+                    .filter(|installed| installed.version().is_none())
+                    .map(|installed| (installed.id.as_str(), None)),
+            );
+        }
+
+        let mut report = PruneReport::default();
+
+        for (id, newer_version) in candidates {
+            // The IDs all came from the list of installed versions.
+            let installed = installed.iter().find(|i| i.id == id).unwrap();
+
+            // Versions that are too new to be deleted aren't reported as protected.
+            if let Some(cutoff) = cutoff {
+                if self.installed_time(installed)? > cutoff {
+                    continue;
+                }
+            }
+
+            // Starting a version locks it until its binary is running, so once the lock is held,
+            // it's either running or safe to delete. Whether it's protected is only checked after
+            // that, so that versions started while pruning are seen.
+            let _lock = if policy.dry_run {
+                None
+            } else {
+                Some(self.fs.lock_version(id, LockMode::Exclusive)?)
+            };
+
+            if let Some(protection) = self.protection(id)? {
+                report.protected.push(ProtectedVersion {
+                    version: id.into(),
+                    protection,
+                });
+
+                continue;
+            }
+
+            // Only the versions that are deleted are measured, since it means reading all of their
+            // files.
+            let size = self.fs.version_size(id).unwrap_or_default();
+
+            // Another monger process may have deleted the version while waiting for the lock.
+            if !policy.dry_run && !self.fs.delete_mongodb_version(id)? {
+                continue;
+            }
+
+            report.reclaimed += size;
+            report.pruned.push(PrunedVersion {
+                version: id.into(),
+                newer_version: newer_version.map(Into::into),
+                size,
+            });
+        }
+
+        Ok(report)
+    }
+
+    /// Why an installed version must not be pruned, if it mustn't. Running processes and
+    /// deployments are looked up each time, since they may have changed while waiting for a lock.
+    fn protection(&self, id: &str) -> Result<Option<Protection>> {
+        let installed = |version: &str| self.installed_version(version).as_deref() == Some(id);
+
+        if let Some(pinned) = self.pinned_version()? {
+            if pinned.source != VersionSource::Default && installed(&pinned.version) {
+                return Ok(Some(Protection::Pinned {
+                    source: pinned.source.to_string(),
+                }));
+            }
+        }
+
+        if self.default_version().is_some_and(installed) {
+            return Ok(Some(Protection::Default));
+        }
+
+        let aliases = self.aliases_of(id);
+
+        if !aliases.is_empty() {
+            return Ok(Some(Protection::Aliased {
+                aliases: aliases.into_iter().map(Into::into).collect(),
+            }));
+        }
+
+        let bin_dir = self.fs.get_version_bin_dir(id)?;
+
+        if running_executables()
+            .iter()
+            .any(|exe| exe.starts_with(&bin_dir))
+            || self.list_deployments()?.iter().any(|deployment| {
+                installed(&deployment.version)
+                    && deployment.members.iter().any(|member| member.is_running())
+            })
+        {
+            return Ok(Some(Protection::Running));
+        }
+
+        Ok(None)
+    }
+
+    /// When a version was installed, according to its manifest if it has one.
+    fn installed_time(&self, installed: &InstalledVersion) -> Result<SystemTime> {
+        if let Some(time) = installed
+            .manifest
+            .as_ref()
+            .and_then(|manifest| humantime::parse_rfc3339(&manifest.installed_at).ok())
+        {
+            return Ok(time);
+        }
+
+        self.fs.version_modified_time(&installed.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{create_dir_all, write},
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    use semver::Version;
+
+    use super::{select_prunable, Protection, PrunePolicy};
+    use crate::{
+        config::Config, fs::MANIFEST_FILE, manifest::Manifest, util::TempDir, Monger, ReleaseKind,
+    };
+
+    /// Creates an installed version with a manifest recording when it was installed.
+    fn install(home_dir: &Path, id: &str, version: Option<&str>, installed_at: SystemTime) {
+        let dir = home_dir.join("mongodb-versions").join(id);
+        create_dir_all(dir.join("bin")).unwrap();
+
+        let mut manifest = Manifest::new(id, "https://example.com/mongodb.tgz", b"");
+        manifest.version = version.map(Into::into);
+        manifest.installed_at = humantime::format_rfc3339_seconds(installed_at).to_string();
+
+        write(dir.join(MANIFEST_FILE), toml::to_string(&manifest).unwrap()).unwrap();
+    }
+
+    fn versions(ids: &[&'static str]) -> Vec<(&'static str, Version, ReleaseKind)> {
+        ids.iter()
+            .map(|id| {
+                let version = Version::parse(id).unwrap();
+                let kind = ReleaseKind::of(&version);
+
+                (*id, version, kind)
+            })
+            .collect()
+    }
+
+    const INSTALLED: &[&str] = &[
+        "3.0.14",
+        "3.0.15",
+        "3.2.10",
+        "3.2.16",
+        "3.4.6-rc0",
+        "3.4.7",
+        "3.4.8-rc1",
+        "3.5.10",
+        "3.5.11",
+        "3.5.12",
+        "3.6.0-rc0",
+        "3.6.0-rc2",
+        "3.6.0-rc10",
+    ];
+
+    #[test]
+    fn keep_newest_stable() {
+        assert_eq!(
+            vec![
+                ("3.0.14", "3.0.15"),
+                ("3.2.10", "3.2.16"),
+                ("3.4.6-rc0", "3.4.7"),
+                ("3.5.10", "3.5.12"),
+                ("3.5.11", "3.5.12"),
+            ],
+            select_prunable(&versions(INSTALLED), 1, false)
+        );
+    }
+
+    #[test]
+    fn keep_several() {
+        assert_eq!(
+            vec![("3.4.6-rc0", "3.4.7"), ("3.5.10", "3.5.12")],
+            select_prunable(&versions(INSTALLED), 2, false)
+        );
+    }
+
+    #[test]
+    fn include_prereleases() {
+        assert_eq!(
+            vec![
+                ("3.0.14", "3.0.15"),
+                ("3.2.10", "3.2.16"),
+                ("3.4.6-rc0", "3.4.8-rc1"),
+                ("3.4.7", "3.4.8-rc1"),
+                ("3.5.10", "3.5.12"),
+                ("3.5.11", "3.5.12"),
+                ("3.6.0-rc0", "3.6.0-rc10"),
+                ("3.6.0-rc2", "3.6.0-rc10"),
+            ],
+            select_prunable(&versions(INSTALLED), 1, true)
+        );
+    }
+
+    #[test]
+    fn custom_ids() {
+        let versions = vec![
+            ("4.4.1", version!(4, 4, 1), ReleaseKind::Stable),
+            ("patched", version!(4, 4, 3), ReleaseKind::Stable),
+        ];
+
+        assert_eq!(
+            vec![("4.4.1", "patched")],
+            select_prunable(&versions, 1, false)
+        );
+    }
+
+    #[test]
+    fn rank_by_kind() {
+        // The release feed doesn't mark 6.0.3 as a production release, so it's neither kept in
+        // place of 6.0.2 nor deleted for being older than it.
+        let versions = vec![
+            ("6.0.1", version!(6, 0, 1), ReleaseKind::Lts),
+            ("6.0.2", version!(6, 0, 2), ReleaseKind::Lts),
+            ("6.0.3", version!(6, 0, 3), ReleaseKind::Development),
+            ("6.1.0", version!(6, 1, 0), ReleaseKind::Rapid),
+            ("6.1.1", version!(6, 1, 1), ReleaseKind::Rapid),
+        ];
+
+        assert_eq!(
+            vec![("6.0.1", "6.0.2"), ("6.1.0", "6.1.1")],
+            select_prunable(&versions, 1, false)
+        );
+
+        // Release candidates rank like the release they precede, so 6.0.4-rc0 is kept in place of
+        // 6.0.2, and 6.0.3 is older than it.
+        let versions = vec![
+            ("6.0.2", version!(6, 0, 2), ReleaseKind::Lts),
+            ("6.0.3", version!(6, 0, 3), ReleaseKind::Development),
+            (
+                "6.0.4-rc0",
+                Version::parse("6.0.4-rc0").unwrap(),
+                ReleaseKind::Prerelease,
+            ),
+        ];
+
+        assert_eq!(
+            vec![("6.0.2", "6.0.4-rc0"), ("6.0.3", "6.0.4-rc0")],
+            select_prunable(&versions, 1, true)
+        );
+    }
+
+    #[test]
+    fn older_than() {
+        let home_dir = TempDir::new("prune");
+
+        let now = SystemTime::now();
+        let last_year = now - Duration::from_secs(365 * 24 * 60 * 60);
+
+        install(&home_dir, "4.2.0", None, now);
+        install(&home_dir, "4.2.1", None, now);
+        install(&home_dir, "4.4.0", None, last_year);
+        install(&home_dir, "4.4.1", None, last_year);
+        install(&home_dir, "custom-old", None, last_year);
+        install(&home_dir, "custom-new", None, now);
+
+        let mut config = Config::default();
+        config.aliases.insert("pinned".into(), "4.2.0".into());
+        let monger = Monger::with_config(&home_dir, home_dir.join("config.toml"), config).unwrap();
+
+        let report = monger
+            .prune(&PrunePolicy {
+                older_than: Some(Duration::from_secs(30 * 24 * 60 * 60)),
+                dry_run: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let pruned: Vec<_> = report
+            .pruned
+            .iter()
+            .map(|pruned| (pruned.version.as_str(), pruned.newer_version.as_deref()))
+            .collect();
+
+        // 4.2.0 is too new to be deleted, so it isn't reported as protected by its alias.
+        assert_eq!(vec![("4.4.0", Some("4.4.1")), ("custom-old", None)], pruned);
+        assert!(report.protected.is_empty());
+
+        // Without an age, custom builds are left alone.
+        let report = monger
+            .prune(&PrunePolicy {
+                dry_run: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(report
+            .pruned
+            .iter()
+            .all(|pruned| pruned.newer_version.is_some()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn running() {
+        let home_dir = TempDir::new("prune-running");
+
+        let last_year = SystemTime::now() - Duration::from_secs(365 * 24 * 60 * 60);
+        install(&home_dir, "4.4.0", None, last_year);
+        install(&home_dir, "4.4.1", None, last_year);
+
+        // Run a process from 4.4.0's bin directory, as if it had been started by monger.
+        let mongod = home_dir.join("mongodb-versions/4.4.0/bin/mongod");
+        std::fs::copy("/bin/sleep", &mongod).unwrap();
+        let mut child = std::process::Command::new(&mongod)
+            .arg("60")
+            .spawn()
+            .unwrap();
+
+        let monger =
+            Monger::with_config(&home_dir, home_dir.join("config.toml"), Config::default())
+                .unwrap();
+        let report = monger.prune(&PrunePolicy::default());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let report = report.unwrap();
+        assert!(report.pruned.is_empty());
+        assert_eq!(1, report.protected.len());
+        assert_eq!("4.4.0", report.protected[0].version);
+        assert_eq!(Protection::Running, report.protected[0].protection);
+        assert!(mongod.exists());
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    Monger,
+};
+
+impl Monger {
+    /// The directory containing the shims, which should be added to the front of `PATH`.
+    pub fn shims_dir(&self) -> PathBuf {
+        self.fs.get_shims_dir()
+    }
+
+    /// Regenerates the shims, creating one for every binary in any installed version and removing
+    /// the ones for binaries that are no longer installed. Each shim is a symlink to `exe`, which
+    /// should be the monger executable. Returns the names of the shims.
+    pub fn rehash(&self, exe: &Path) -> Result<Vec<String>> {
+        let binaries = self.fs.list_version_binaries()?;
+        self.fs.write_shims(exe, &binaries)?;
+
+        Ok(binaries.into_iter().collect())
+    }
+
+    /// Regenerates the shims if they've been generated before, so that they stay up to date as
+    /// versions are installed and deleted.
+    pub fn refresh_shims(&self, exe: &Path) -> Result<()> {
+        if self.fs.get_shims_dir().is_dir() {
+            self.rehash(exe)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn list_shims(&self) -> Result<Vec<String>> {
+        self.fs.list_shims()
+    }
+
+    /// Whether a shim exists for the given binary.
+    pub fn is_shim(&self, binary: &str) -> bool {
+        self.fs.get_shims_dir().join(binary).exists()
+    }
+
+    /// Runs a binary of the pinned version in place of the current process, as the shim for it.
+    /// Only returns if an error occurs.
+    pub fn exec_shim(&self, binary: &str, args: Vec<OsString>) -> Error {
+        let version = match self.resolve_version(None) {
+            Ok(version) => version,
+            Err(e) => return e,
+        };
+
+        // The system version is looked up in PATH, so the shims have to be taken out of it first to
+        // avoid running the shim again.
+        if self.fs.is_system(&version) {
+            if let Some(path) = env::var_os("PATH") {
+                let shims_dir = self.fs.get_shims_dir();
+                let path = env::split_paths(&path).filter(|dir| *dir != shims_dir);
+
+                // The entries came from PATH in the first place, so they can always be joined.
+                if let Ok(path) = env::join_paths(path) {
+                    env::set_var("PATH", path);
+                }
+            }
+        }
+
+        self.exec_command(binary, args, &version)
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::{self, Display},
+    io::{BufReader, Read},
+};
+
+use semver::{Identifier, Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+use crate::util::parse_major_minor_version;
+
+/// A way of choosing a MongoDB release, which is resolved against either the releases available
+/// to download (for `monger get`) or the installed versions (everywhere else a version is looked
+/// up).
+#[derive(Clone, Debug, PartialEq)]
+pub enum VersionSpec {
+    /// A single release, e.g. `4.4.1`.
+    Exact(Version),
+
+    /// The newest production release (`latest`), i.e. the newest stable, LTS, or rapid release.
+    Latest,
+
+    /// The newest release of any kind, including development releases (`latest-dev`).
+    LatestDev,
+
+    /// The newest long-term support release (`lts`).
+    Lts,
+
+    /// The newest rapid release (`rapid`).
+    Rapid,
+
+    /// The newest release matching a semver constraint, e.g. `>=4.4, <5`, `~5.0`, or `^6`. A
+    /// major and minor version like `4.4` means `~4.4`, i.e. its newest patch release.
+    Constraint(VersionReq),
+
+    /// The nightly build of the development branch (`nightly`) or of a release branch (e.g.
+    /// `4.4-nightly`), which isn't a release and so can only be downloaded, not resolved.
+    Nightly(Option<(u64, u64)>),
+}
+
+impl VersionSpec {
+    /// Parses a version spec, returning `None` if the string isn't one (e.g. if it's the ID of a
+    /// custom installation).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "latest" => return Some(Self::Latest),
+            "latest-dev" => return Some(Self::LatestDev),
+            "lts" => return Some(Self::Lts),
+            "rapid" => return Some(Self::Rapid),
+            "nightly" => return Some(Self::Nightly(None)),
+            _ => {}
+        }
+
+        if let Some(branch) = s.strip_suffix("-nightly") {
+            return parse_major_minor_version(branch).map(|branch| Self::Nightly(Some(branch)));
+        }
+
+        if let Ok(version) = Version::parse(s) {
+            return Some(Self::Exact(version));
+        }
+
+        if let Some((major, minor)) = parse_major_minor_version(s) {
+            return VersionReq::parse(&format!("~{}.{}", major, minor))
+                .ok()
+                .map(Self::Constraint);
+        }
+
+        // `VersionReq` also accepts things like "", "x", and "6-debug", which are more likely to be
+        // IDs than constraints, so only a major version like "6" or something starting with an
+        // operator is treated as one.
+        let is_major = !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+        if !is_major && !s.starts_with(|c: char| "<>=~^".contains(c)) {
+            return None;
+        }
+
+        VersionReq::parse(s).ok().map(Self::Constraint)
+    }
+
+    /// Whether a release satisfies the spec, using the catalog to tell which kind of release it is.
+    /// Builds with metadata only match exact versions, and so do pre-releases (e.g. release
+    /// candidates), except that `latest-dev` always matches them and constraints match them if
+    /// `prereleases` is true.
+    pub fn matches(&self, version: &Version, catalog: &ReleaseCatalog, prereleases: bool) -> bool {
+        if let Self::Exact(exact) = self {
+            return exact == version;
+        }
+
+        if !version.build.is_empty() {
+            return false;
+        }
+
+        if !version.pre.is_empty() {
+            return match self {
+                Self::LatestDev => true,
+                Self::Constraint(req) if prereleases => req.matches(&Version {
+                    pre: Vec::new(),
+                    ..version.clone()
+                }),
+                _ => false,
+            };
+        }
+
+        match self {
+            Self::Exact(_) | Self::Nightly(_) => false,
+            Self::Latest => catalog.kind(version).is_production(),
+            Self::LatestDev => true,
+            Self::Lts => catalog.kind(version) == ReleaseKind::Lts,
+            Self::Rapid => catalog.kind(version) == ReleaseKind::Rapid,
+            Self::Constraint(req) => req.matches(version),
+        }
+    }
+
+    /// The newest of the given releases that satisfies the spec. A pre-release is only chosen if
+    /// no other release matches, even an older one (e.g. `>=7` prefers 7.0.0 to 7.0.1-rc0), except
+    /// by `latest-dev`.
+    pub fn select<'a>(
+        &self,
+        versions: impl IntoIterator<Item = &'a Version>,
+        catalog: &ReleaseCatalog,
+        prereleases: bool,
+    ) -> Option<&'a Version> {
+        let prefer_stable = *self != Self::LatestDev;
+
+        versions
+            .into_iter()
+            .filter(|version| self.matches(version, catalog, prereleases))
+            .max_by(|a, b| {
+                let stable = |v: &Version| prefer_stable && v.pre.is_empty();
+
+                stable(a)
+                    .cmp(&stable(b))
+                    .then_with(|| compare_versions(a, b))
+            })
+    }
+}
+
+impl VersionSpec {
+    /// Whether resolving the spec depends on which kind of release each version is.
+    pub(crate) fn needs_catalog(&self) -> bool {
+        matches!(self, Self::Latest | Self::Lts | Self::Rapid)
+    }
+}
+
+/// Orders versions like semver does, except that pre-release tags that end in a number are ordered
+/// by it, so that e.g. `7.0.0-rc10` is newer than `7.0.0-rc2`.
+pub(crate) fn compare_versions(a: &Version, b: &Version) -> Ordering {
+    let numbers = |v: &Version| (v.major, v.minor, v.patch);
+
+    numbers(a)
+        .cmp(&numbers(b))
+        .then_with(|| match (a.pre.is_empty(), b.pre.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => a
+                .pre
+                .iter()
+                .map(split_identifier)
+                .cmp(b.pre.iter().map(split_identifier)),
+        })
+        .then_with(|| a.cmp(b))
+}
+
+/// Splits a pre-release identifier like `rc10` into its text and trailing number.
+fn split_identifier(identifier: &Identifier) -> (String, Option<u64>) {
+    match identifier {
+        Identifier::Numeric(n) => (String::new(), Some(*n)),
+        Identifier::AlphaNumeric(s) => {
+            let text = s.trim_end_matches(|c: char| c.is_ascii_digit());
+
+            (text.to_string(), s[text.len()..].parse().ok())
+        }
+    }
+}
+
+/// The first major version released under MongoDB's current release model, in which each major
+/// release is supported long-term and followed by quarterly rapid releases. Before it, even minor
+/// versions were production releases and odd ones were development releases.
+const FIRST_RAPID_RELEASE_MAJOR: u64 = 5;
+
+/// What kind of release a version is, which decides what `latest`, `lts`, and `rapid` resolve to
+/// and which releases `Monger::prune` keeps.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseKind {
+    /// A production release from before 5.0, i.e. one with an even minor version (e.g. 4.4.1).
+    Stable,
+
+    /// A long-term support release from 5.0 on, which are usually major releases (e.g. 6.0.2).
+    Lts,
+
+    /// One of the quarterly releases between major releases from 5.0 on (e.g. 6.1.0), which are
+    /// only supported until the next one.
+    Rapid,
+
+    /// A release of a development series, i.e. one with an odd minor version from before 5.0
+    /// (e.g. 4.3.1), or a later one that MongoDB's release feed doesn't mark as a production
+    /// release.
+    Development,
+
+    /// A release candidate or other pre-release (e.g. 7.0.0-rc1), or a nightly build.
+    Prerelease,
+}
+
+impl ReleaseKind {
+    /// Classifies a release by its version alone, for when MongoDB's release feed doesn't say. From
+    /// 5.0 on, this assumes that major releases are LTS releases and every other one is a rapid
+    /// release.
+    pub fn of(version: &Version) -> Self {
+        Self::classify(version, None)
+    }
+
+    /// Classifies a release. Releases before 5.0 are classified by their minor version, and later
+    /// ones by what MongoDB's release feed says about them, if anything.
+    pub(crate) fn classify(version: &Version, flags: Option<&ReleaseFlags>) -> Self {
+        if !version.pre.is_empty() {
+            return Self::Prerelease;
+        }
+
+        if version.major < FIRST_RAPID_RELEASE_MAJOR {
+            return if version.minor.is_multiple_of(2) {
+                Self::Stable
+            } else {
+                Self::Development
+            };
+        }
+
+        match flags {
+            Some(flags) if flags.lts_release => Self::Lts,
+            Some(flags) if flags.production_release => Self::Rapid,
+            Some(_) => Self::Development,
+            None if version.minor == 0 => Self::Lts,
+            None => Self::Rapid,
+        }
+    }
+
+    /// Whether the release is meant for production use, i.e. it's a stable, LTS, or rapid
+    /// release.
+    pub fn is_production(self) -> bool {
+        matches!(self, Self::Stable | Self::Lts | Self::Rapid)
+    }
+}
+
+impl Display for ReleaseKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Stable => write!(f, "stable"),
+            Self::Lts => write!(f, "lts"),
+            Self::Rapid => write!(f, "rapid"),
+            Self::Development => write!(f, "development"),
+            Self::Prerelease => write!(f, "pre-release"),
+        }
+    }
+}
+
+/// What MongoDB's release feed says about a release.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub(crate) struct ReleaseFlags {
+    #[serde(default)]
+    pub(crate) production_release: bool,
+
+    #[serde(default)]
+    pub(crate) lts_release: bool,
+}
+
+/// The kinds of releases known from MongoDB's release feed, or from the manifests of installed
+/// versions, which record it. Releases it doesn't know are classified by `ReleaseKind::of`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReleaseCatalog {
+    kinds: BTreeMap<Version, ReleaseKind>,
+}
+
+impl ReleaseCatalog {
+    pub(crate) fn insert(&mut self, version: Version, kind: ReleaseKind) {
+        self.kinds.insert(version, kind);
+    }
+
+    /// The kind of a release, if the catalog knows it.
+    pub(crate) fn get(&self, version: &Version) -> Option<ReleaseKind> {
+        self.kinds.get(version).copied()
+    }
+
+    /// The kind of a release, falling back to classifying it by its version.
+    pub fn kind(&self, version: &Version) -> ReleaseKind {
+        self.get(version)
+            .unwrap_or_else(|| ReleaseKind::of(version))
+    }
+
+    /// Reads MongoDB's release feed, skipping releases whose version isn't valid semver.
+    pub(crate) fn from_feed(feed: impl Read) -> serde_json::Result<Self> {
+        let feed: ReleaseFeed = serde_json::from_reader(BufReader::new(feed))?;
+        let mut catalog = Self::default();
+
+        for release in feed.versions {
+            if let Ok(version) = Version::parse(&release.version) {
+                let kind = ReleaseKind::classify(&version, Some(&release.flags));
+                catalog.insert(version, kind);
+            }
+        }
+
+        Ok(catalog)
+    }
+}
+
+/// MongoDB's release feed, which lists every release with where to download it from.
+#[derive(Deserialize)]
+struct ReleaseFeed {
+    versions: Vec<FeedRelease>,
+}
+
+#[derive(Deserialize)]
+struct FeedRelease {
+    version: String,
+
+    #[serde(flatten)]
+    flags: ReleaseFlags,
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::{ReleaseCatalog, ReleaseFlags, ReleaseKind, VersionSpec};
+
+    fn select(spec: &str, versions: &[Version], prereleases: bool) -> Option<String> {
+        VersionSpec::parse(spec)
+            .unwrap()
+            .select(versions, &ReleaseCatalog::default(), prereleases)
+            .map(ToString::to_string)
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Some(VersionSpec::Exact(version!(4, 4, 1))),
+            VersionSpec::parse("4.4.1")
+        );
+        assert_eq!(Some(VersionSpec::Lts), VersionSpec::parse("lts"));
+        assert!(matches!(
+            VersionSpec::parse(">=4.4, <5"),
+            Some(VersionSpec::Constraint(_))
+        ));
+        assert!(matches!(
+            VersionSpec::parse("4.4"),
+            Some(VersionSpec::Constraint(_))
+        ));
+        assert_eq!(
+            Some(VersionSpec::Nightly(None)),
+            VersionSpec::parse("nightly")
+        );
+        assert_eq!(
+            Some(VersionSpec::Nightly(Some((4, 4)))),
+            VersionSpec::parse("4.4-nightly")
+        );
+        assert_eq!(None, VersionSpec::parse("4-nightly"));
+        assert!(matches!(
+            VersionSpec::parse("6"),
+            Some(VersionSpec::Constraint(_))
+        ));
+        assert_eq!(None, VersionSpec::parse("6-debug"));
+        assert_eq!(None, VersionSpec::parse("4.x"));
+        assert_eq!(None, VersionSpec::parse("my-build"));
+        assert_eq!(None, VersionSpec::parse("x"));
+        assert_eq!(None, VersionSpec::parse(""));
+    }
+
+    #[test]
+    fn select_newest_match() {
+        let versions: Vec<_> = [
+            "3.6.23",
+            "4.2.8",
+            "4.4.1",
+            "4.4.10",
+            "4.5.0",
+            "5.0.3",
+            "5.1.1",
+            "6.0.2",
+            "6.1.0",
+            "6.3.2",
+            "7.0.0-rc1",
+        ]
+        .iter()
+        .map(|v| Version::parse(v).unwrap())
+        .collect();
+
+        assert_eq!(Some("4.4.10".into()), select("4.4", &versions, false));
+        assert_eq!(Some("4.4.10".into()), select("~4.4", &versions, false));
+        assert_eq!(Some("4.5.0".into()), select(">=4.4, <5", &versions, false));
+        assert_eq!(Some("6.3.2".into()), select("^6", &versions, false));
+        assert_eq!(Some("6.3.2".into()), select("6", &versions, false));
+        assert_eq!(Some("6.0.2".into()), select("lts", &versions, false));
+        assert_eq!(Some("6.3.2".into()), select("rapid", &versions, false));
+        assert_eq!(Some("6.3.2".into()), select("latest", &versions, false));
+        assert_eq!(
+            Some("7.0.0-rc1".into()),
+            select("latest-dev", &versions, false)
+        );
+        assert_eq!(
+            Some("7.0.0-rc1".into()),
+            select("7.0.0-rc1", &versions, false)
+        );
+        assert_eq!(None, select("4.6", &versions, false));
+        assert_eq!(None, select("7", &versions, false));
+        assert_eq!(None, select("nightly", &versions, false));
+    }
+
+    #[test]
+    fn release_kinds() {
+        let kind = |version: &str| ReleaseKind::of(&Version::parse(version).unwrap());
+
+        assert_eq!(ReleaseKind::Stable, kind("4.4.1"));
+        assert_eq!(ReleaseKind::Development, kind("4.3.2"));
+        assert_eq!(ReleaseKind::Lts, kind("5.0.3"));
+        assert_eq!(ReleaseKind::Rapid, kind("5.1.1"));
+        assert_eq!(ReleaseKind::Rapid, kind("6.2.0"));
+        assert_eq!(ReleaseKind::Prerelease, kind("7.0.0-rc1"));
+        assert_eq!(ReleaseKind::Prerelease, kind("4.4.0-rc13"));
+    }
+
+    #[test]
+    fn release_kinds_from_feed() {
+        let classify = |version: &str, production_release, lts_release| {
+            ReleaseKind::classify(
+                &Version::parse(version).unwrap(),
+                Some(&ReleaseFlags {
+                    production_release,
+                    lts_release,
+                }),
+            )
+        };
+
+        assert_eq!(ReleaseKind::Lts, classify("8.0.4", true, true));
+        assert_eq!(ReleaseKind::Rapid, classify("7.3.1", true, false));
+        assert_eq!(ReleaseKind::Development, classify("7.2.0", false, false));
+
+        // Releases before 5.0 are classified by their minor version regardless.
+        assert_eq!(ReleaseKind::Development, classify("4.3.2", true, false));
+        assert_eq!(ReleaseKind::Stable, classify("4.4.1", true, false));
+
+        let versions: Vec<_> = ["6.0.2", "7.0.5", "7.1.1", "7.2.0"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+        let mut catalog = ReleaseCatalog::default();
+        catalog.insert(version!(7, 2, 0), ReleaseKind::Development);
+
+        let select = |spec: &str| {
+            VersionSpec::parse(spec)
+                .unwrap()
+                .select(&versions, &catalog, false)
+                .map(ToString::to_string)
+        };
+
+        assert_eq!(Some("7.1.1".into()), select("latest"));
+        assert_eq!(Some("7.1.1".into()), select("rapid"));
+        assert_eq!(Some("7.0.5".into()), select("lts"));
+        assert_eq!(Some("7.2.0".into()), select("latest-dev"));
+    }
+
+    #[test]
+    fn release_feed() {
+        let feed = r#"{
+            "versions": [
+                { "version": "8.0.4", "production_release": true, "lts_release": true },
+                { "version": "7.3.1", "production_release": true, "lts_release": false },
+                { "version": "7.2.0-rc1", "production_release": false },
+                { "version": "4.4.29", "production_release": true, "downloads": [] },
+                { "version": "not-a-version" }
+            ]
+        }"#;
+        let catalog = ReleaseCatalog::from_feed(feed.as_bytes()).unwrap();
+
+        assert_eq!(Some(ReleaseKind::Lts), catalog.get(&version!(8, 0, 4)));
+        assert_eq!(Some(ReleaseKind::Rapid), catalog.get(&version!(7, 3, 1)));
+        assert_eq!(Some(ReleaseKind::Stable), catalog.get(&version!(4, 4, 29)));
+        assert_eq!(
+            ReleaseKind::Prerelease,
+            catalog.kind(&Version::parse("7.2.0-rc1").unwrap())
+        );
+
+        // Releases missing from the feed are classified by their version.
+        assert_eq!(None, catalog.get(&version!(6, 0, 2)));
+        assert_eq!(ReleaseKind::Lts, catalog.kind(&version!(6, 0, 2)));
+    }
+
+    #[test]
+    fn latest_before_rapid_releases() {
+        let versions: Vec<_> = ["4.0.20", "4.2.8", "4.3.6", "4.4.0-rc1"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+
+        assert_eq!(Some("4.2.8".into()), select("latest", &versions, false));
+        assert_eq!(
+            Some("4.4.0-rc1".into()),
+            select("latest-dev", &versions, false)
+        );
+        assert_eq!(None, select("lts", &versions, false));
+    }
+
+    #[test]
+    fn select_prereleases() {
+        let versions: Vec<_> = ["6.0.2", "7.0.0-rc1", "7.0.0-rc10", "7.0.0-rc2"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+
+        assert_eq!(None, select("7.0", &versions, false));
+        assert_eq!(Some("7.0.0-rc10".into()), select("7.0", &versions, true));
+        assert_eq!(Some("6.0.2".into()), select(">=6", &versions, true));
+        assert_eq!(
+            Some("7.0.0-rc10".into()),
+            select("latest-dev", &versions, false)
+        );
+        assert_eq!(Some("6.0.2".into()), select("latest", &versions, true));
+        assert_eq!(Some("6.0.2".into()), select("lts", &versions, true));
+    }
+
+    #[test]
+    fn prefer_stable_releases() {
+        let versions: Vec<_> = ["6.3.2", "7.0.0", "7.0.1-rc0", "7.1.0-rc2"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+
+        assert_eq!(Some("7.0.0".into()), select("7.0", &versions, true));
+        assert_eq!(Some("7.0.0".into()), select(">=7", &versions, true));
+        assert_eq!(Some("7.1.0-rc2".into()), select("7.1", &versions, true));
+        assert_eq!(
+            Some("7.1.0-rc2".into()),
+            select("latest-dev", &versions, true)
+        );
+    }
+}
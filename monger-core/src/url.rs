@@ -20,6 +20,7 @@ pub struct Url {
     base: String,
     filename: String,
     dirname: String,
+    version: String,
 }
 
 impl Url {
@@ -30,6 +31,25 @@ impl Url {
     pub fn dirname(&self) -> String {
         self.dirname.clone()
     }
+
+    /// Downloads a nightly build (e.g. "latest" or "v4.4-latest") instead of the release, which
+    /// has the same name except for the version.
+    pub fn set_build(&mut self, build: &str) {
+        for name in [&mut self.filename, &mut self.dirname].iter_mut() {
+            if let Some(i) = name.rfind(&self.version) {
+                name.replace_range(i..i + self.version.len(), build);
+            }
+        }
+    }
+
+    /// Downloads from a mirror with the same layout as `https://fastdl.mongodb.org` instead.
+    pub fn set_mirror(&mut self, mirror: &str) {
+        let official = format!("{}://{}", SCHEME, DOMAIN);
+
+        if let Some(path) = self.base.strip_prefix(&official) {
+            self.base = format!("{}{}", mirror.trim_end_matches('/'), path);
+        }
+    }
 }
 
 impl From<Url> for String {
@@ -106,6 +126,7 @@ impl<'a> UrlBuilder<'a> {
             base,
             filename,
             dirname,
+            version: self.version.to_string(),
         }
     }
 }
@@ -1,10 +1,13 @@
 use std::str::FromStr;
+#[cfg(test)]
+use std::{
+    fs::{create_dir_all, remove_dir_all},
+    ops::Deref,
+    path::{Path, PathBuf},
+};
 
 use lazy_static::lazy_static;
 use regex::Regex;
-use semver::Version;
-
-use crate::error::{Error, Result};
 
 lazy_static! {
     static ref VERSION_WITHOUT_PATCH: Regex = Regex::new(r"^(\d+)\.(\d+)$").unwrap();
@@ -42,27 +45,42 @@ macro_rules! version {
     }};
 }
 
-pub fn select_newer_version(existing: Option<Version>, found: Version) -> Version {
-    if let Some(version) = existing {
-        if version > found {
-            return version;
-        }
-    }
-
-    found
-}
-
 pub fn parse_major_minor_version(version: &str) -> Option<(u64, u64)> {
     VERSION_WITHOUT_PATCH
         .captures(version)
         .map(|c| (c[1].parse().unwrap(), c[2].parse().unwrap()))
 }
 
-pub fn parse_version(version: &str) -> Result<Version> {
-    Version::parse(version).map_err(|_| {
-        Error::VersionNotFound {
-            version: version.into(),
-        }
-        .into()
-    })
+/// An empty directory for a test's files, which is deleted when it's dropped so that it's cleaned
+/// up even if the test fails.
+#[cfg(test)]
+pub(crate) struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    /// Creates the directory, named after the test and the current process so that concurrent
+    /// test runs don't share it.
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("monger-{}-{}", name, std::process::id()));
+        let _ = remove_dir_all(&path);
+        create_dir_all(&path).unwrap();
+
+        Self(path)
+    }
+}
+
+#[cfg(test)]
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.0);
+    }
 }
@@ -0,0 +1,95 @@
+use std::{
+    env,
+    ffi::OsStr,
+    fs::metadata,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    Monger,
+};
+
+fn is_executable(path: &Path) -> bool {
+    metadata(path)
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+/// Finds an executable in the directories of a PATH, skipping `skip_dir`.
+fn find_in_path(path: &OsStr, binary: &str, skip_dir: &Path) -> Option<PathBuf> {
+    env::split_paths(path)
+        .filter(|dir| dir != skip_dir)
+        .map(|dir| dir.join(binary))
+        .find(|path| is_executable(path))
+}
+
+impl Monger {
+    /// Finds a binary on the PATH, which is where the binaries of the "system" version are run
+    /// from. The shims are skipped, since they'd run the pinned version instead.
+    pub fn find_system_binary(&self, binary: &str) -> Option<PathBuf> {
+        find_in_path(&env::var_os("PATH")?, binary, &self.fs.get_shims_dir())
+    }
+
+    /// The absolute path of a binary of a version, resolving aliases and major and minor versions
+    /// the same way as when running it.
+    pub fn binary_path(&self, id: &str, binary: &str) -> Result<PathBuf> {
+        let not_found = || Error::BinaryNotFound {
+            binary: binary.into(),
+            version: id.into(),
+        };
+
+        let version = self
+            .fs
+            .get_newest_matching_version(id)
+            .map_err(|_| not_found())?;
+
+        if version == "system" {
+            return self.find_system_binary(binary).ok_or_else(not_found);
+        }
+
+        let path = self.fs.get_version_bin_dir(&version)?.join(binary);
+
+        if is_executable(&path) {
+            Ok(path)
+        } else {
+            Err(not_found())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        fs::{create_dir_all, set_permissions, write, Permissions},
+        os::unix::fs::PermissionsExt,
+    };
+
+    use super::find_in_path;
+    use crate::util::TempDir;
+
+    #[test]
+    fn skips_shims_and_non_executables() {
+        let root = TempDir::new("which");
+
+        let shims = root.join("shims");
+        let plain = root.join("plain");
+        let bin = root.join("bin");
+
+        for (dir, mode) in [(&shims, 0o755), (&plain, 0o644), (&bin, 0o755)] {
+            create_dir_all(dir).unwrap();
+            write(dir.join("mongod"), "#!/bin/sh\n").unwrap();
+            set_permissions(dir.join("mongod"), Permissions::from_mode(mode)).unwrap();
+        }
+
+        let path = env::join_paths([&shims, &plain, &bin]).unwrap();
+
+        assert_eq!(
+            Some(bin.join("mongod")),
+            find_in_path(&path, "mongod", &shims)
+        );
+        assert_eq!(None, find_in_path(&path, "mongos", &shims));
+    }
+}
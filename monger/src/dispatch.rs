@@ -1,51 +1,614 @@
-use anyhow::Result;
-use monger_core::Monger;
+use std::{
+    env,
+    fmt::Write as _,
+    io::{self, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, Context, Result};
+use monger_core::{
+    deployment::{
+        Auth, ClusterTopology, DeploymentFile, DeploymentState, SecurityOptions, Topology, UpAction,
+    },
+    parse_version_list, Installation, Manifest, Monger, PrunePolicy, VersionInfo,
+};
 use self_update::backends::github::Update;
+use serde_json::{json, Value};
 
-use crate::{util::file_exists_in_path, Defaults, Options};
+use crate::{
+    output::{
+        deployment_json, info_json, installation_json, installations_json, list_json,
+        list_remote_json, prune_json, ListedVersion, Output,
+    },
+    shell::Shell,
+    Alias, Cluster, Config, Data, Defaults, Options, Security, Shims, Snapshot,
+};
 
 impl Options {
-    pub(super) fn dispatch(self) -> Result<()> {
-        let monger = Monger::new()?;
+    pub(super) fn dispatch(self, output: Output) -> Result<()> {
+        let mut monger = Monger::new()?;
+        monger.set_event_handler(move |event| output.event(event));
+
+        let changes_versions = matches!(
+            self,
+            Self::Delete { .. } | Self::Download { .. } | Self::Get { .. } | Self::Prune { .. }
+        );
+
+        let mut failed = false;
 
         match self {
+            Self::Alias(Alias::Create { name, id }) => {
+                let target = monger.create_alias(&name, &id)?;
+                output.print(
+                    format!("Created alias {} for {}", name, target),
+                    json!({ "alias": name, "version": target }),
+                );
+            }
+            Self::Alias(Alias::Delete { name }) => {
+                monger.delete_alias(&name)?;
+                output.print(
+                    format!("Deleted alias {}", name),
+                    json!({ "alias": name, "deleted": true }),
+                );
+            }
+            Self::Alias(Alias::List) => {
+                let aliases = monger.list_aliases();
+
+                output.print(
+                    format_list(
+                        "aliases",
+                        aliases
+                            .iter()
+                            .map(|(name, target)| format!("{} -> {}", name, target)),
+                    ),
+                    json!({
+                        "aliases": aliases
+                            .iter()
+                            .map(|(name, target)| json!({ "name": name, "version": target }))
+                            .collect::<Vec<_>>(),
+                    }),
+                );
+            }
             Self::Clear { id } => {
-                if monger.clear_database_files(&id)? {
-                    println!("Cleared database files of {}", id);
+                let cleared = monger.clear_database_files(&id)?;
+                let json = json!({ "version": id, "cleared": cleared });
+
+                if cleared {
+                    output.print(format!("Cleared database files of {}", id), json);
+                } else {
+                    output.print_json(json);
                 }
             }
-            Self::Delete { id } => monger.delete_mongodb_version(&id)?,
-            Self::Defaults(Defaults::Clear) => {
-                if monger.clear_default_args()? {
-                    println!("Cleared default args");
+            Self::Cluster(Cluster::List) => list_clusters(&monger, output)?,
+            Self::Cluster(Cluster::Start {
+                id,
+                file,
+                name,
+                port,
+                mongos,
+                config_servers,
+                shards,
+                shard_members,
+                standalone_shards,
+                security,
+                mongod_args,
+            }) => {
+                let topology = match (file, id) {
+                    (Some(file), _) => ClusterTopology::from_file(file)?,
+                    (None, Some(id)) => {
+                        let mut topology = ClusterTopology::new(&name, &id);
+                        topology.port = port;
+                        topology.mongos = mongos;
+                        topology.config_servers = config_servers;
+                        topology.mongod_args = mongod_args;
+                        topology.shards.count = shards;
+                        topology.shards.members = shard_members;
+                        topology.shards.replica_set = !standalone_shards;
+                        topology.auth = security.auth();
+                        topology.tls = security.tls;
+                        topology
+                    }
+                    (None, None) => invariant!("structopt requires either an ID or a file"),
+                };
+
+                let state = monger.start_deployment(&Topology::Sharded(topology))?;
+                output.print(
+                    format!("started cluster {}:\n    {}", state.name, state.uri),
+                    deployment_json(&state),
+                );
+            }
+            Self::Cluster(Cluster::Stop { name }) => {
+                monger.stop_deployment(&name, true)?;
+                output.print(
+                    format!("Stopped cluster {}", name),
+                    json!({ "name": name, "stopped": true }),
+                );
+            }
+            Self::Delete { id } => {
+                let deleted = monger.delete_mongodb_version(&id)?;
+                let json = json!({ "version": id, "deleted": deleted });
+
+                if deleted {
+                    output.print(format!("Deleted version {}", id), json);
+                } else {
+                    output.print_json(json);
                 }
             }
-            Self::Defaults(Defaults::Get) => match monger.get_default_args()? {
-                Some(args) => println!("default arguments:\n    {}", args),
-                None => println!("no default arguments exist"),
-            },
-            Self::Defaults(Defaults::Set { args }) => {
-                let args = args.join(" ");
-                let trimmed_args = args.trim();
+            Self::Config(Config::Get { key }) => {
+                let value = monger.get_config(&key)?;
+                let json = json!({ "key": key, "value": value });
 
-                if !trimmed_args.is_empty() {
-                    monger.set_default_args(trimmed_args)?;
+                match value {
+                    Some(value) => output.print(value, json),
+                    None => output.print(format!("{} is not set", key), json),
+                }
+            }
+            Self::Config(Config::List) => {
+                let settings = monger.list_config()?;
+                let text = settings
+                    .iter()
+                    .map(|(key, value)| format!("{} = {}", key, value))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                output.print(
+                    text,
+                    json!({
+                        "settings": settings
+                            .into_iter()
+                            .map(|(key, value)| (key, Value::String(value)))
+                            .collect::<serde_json::Map<_, _>>(),
+                    }),
+                );
+            }
+            Self::Config(Config::Set { key, value }) => {
+                monger.set_config(&key, &value)?;
+                let value = monger.get_config(&key)?;
+
+                output.print(
+                    format!("Set {}", key),
+                    json!({ "key": key, "value": value }),
+                );
+            }
+            Self::Config(Config::Unset { key }) => {
+                monger.unset_config(&key)?;
+                output.print(
+                    format!("Unset {}", key),
+                    json!({ "key": key, "value": Value::Null }),
+                );
+            }
+            Self::Data(Data::Clear { name }) => {
+                monger.clear_data_dir(&name)?;
+                output.print(
+                    format!("Cleared data directory {}", name),
+                    json!({ "name": name, "cleared": true }),
+                );
+            }
+            Self::Data(Data::Delete { name }) => {
+                monger.delete_data_dir(&name)?;
+                output.print(
+                    format!("Deleted data directory {}", name),
+                    json!({ "name": name, "deleted": true }),
+                );
+            }
+            Self::Data(Data::List) => {
+                let data_dirs = monger.list_data_dirs()?;
 
-                    println!("default arguments set to:\n    {}", trimmed_args);
+                output.print(
+                    format_list(
+                        "data directories",
+                        data_dirs.iter().map(|data_dir| match data_dir.version {
+                            Some(ref version) => {
+                                format!("{} (last used by {})", data_dir.name, version)
+                            }
+                            None => data_dir.name.clone(),
+                        }),
+                    ),
+                    json!({
+                        "data_dirs": data_dirs
+                            .iter()
+                            .map(|data_dir| {
+                                json!({ "name": data_dir.name, "version": data_dir.version })
+                            })
+                            .collect::<Vec<_>>(),
+                    }),
+                );
+            }
+            Self::Default { id: Some(id) } => {
+                monger.set_default_version(&id)?;
+                output.print(
+                    format!("Set the default version to {}", id),
+                    json!({ "default": id }),
+                );
+            }
+            Self::Default { id: None } => {
+                let default = monger.default_version();
+                let json = json!({ "default": default });
+
+                match default {
+                    Some(id) => output.print(id, json),
+                    None => output.print("no default version is set", json),
+                }
+            }
+            Self::Defaults(Defaults::Clear { scope }) => {
+                let cleared = monger.clear_default_args(scope.as_deref())?;
+                let json = json!({ "scope": scope, "cleared": cleared });
+
+                if cleared {
+                    output.print("Cleared default args", json);
+                } else {
+                    output.print_json(json);
                 }
             }
+            Self::Defaults(Defaults::Get { scope }) => {
+                let args = monger.get_default_args(scope.as_deref())?;
+                let json = json!({ "scope": scope, "args": args });
+
+                match args {
+                    Some(ref args) => output.print(
+                        format!("default arguments:\n    {}", format_args(args)),
+                        json,
+                    ),
+                    None => output.print("no default arguments exist", json),
+                }
+            }
+            Self::Defaults(Defaults::List) => {
+                let defaults = monger.list_default_args()?;
+                let mut text = String::new();
+
+                if defaults.global.is_empty() && defaults.versions.is_empty() {
+                    text.push_str("no default arguments exist");
+                }
+
+                if !defaults.global.is_empty() {
+                    let _ = write!(text, "global:\n    {}", format_args(&defaults.global));
+                }
+
+                for (scope, args) in &defaults.versions {
+                    if !text.is_empty() {
+                        text.push('\n');
+                    }
+
+                    let _ = write!(text, "{}:\n    {}", scope, format_args(args));
+                }
+
+                output.print(
+                    text,
+                    json!({ "global": defaults.global, "versions": defaults.versions }),
+                );
+            }
+            Self::Defaults(Defaults::Set { scope, args }) => {
+                let args: Vec<_> = args.into_iter().filter(|arg| !arg.is_empty()).collect();
+                let json = json!({ "scope": scope, "args": args });
+
+                if args.is_empty() {
+                    output.print_json(json);
+                } else {
+                    let formatted = format_args(&args);
+                    monger.set_default_args(scope.as_deref(), args)?;
+
+                    output.print(
+                        format!("default arguments set to:\n    {}", formatted),
+                        json,
+                    );
+                }
+            }
+            Self::Defaults(Defaults::Show { id }) => {
+                let args = monger.resolve_default_args(&id, &[])?;
+                let text = if args.is_empty() {
+                    format!("no default arguments apply to {}", id)
+                } else {
+                    format!("default arguments for {}:\n    {}", id, format_args(&args))
+                };
+
+                output.print(text, json!({ "version": id, "args": args }));
+            }
+            Self::Doctor { id, fix, yes } => {
+                if fix && !yes && output.is_json() {
+                    return Err(anyhow!(
+                        "--fix needs --yes with --output json, since it can't ask for confirmation"
+                    ));
+                }
+
+                let diagnoses = monger.diagnose(id.as_deref())?;
+                let blocking = diagnoses.iter().filter(|d| d.is_blocking()).count();
+
+                if !output.is_json() {
+                    for diagnosis in &diagnoses {
+                        println!(
+                            "{}: {}: {}",
+                            diagnosis.severity, diagnosis.check, diagnosis.message
+                        );
+
+                        if let Some(ref hint) = diagnosis.hint {
+                            println!("    hint: {}", hint);
+                        }
+                    }
+                }
+
+                let garbage = monger.find_garbage()?;
+
+                if !output.is_json() && !garbage.is_empty() {
+                    let things = if garbage.len() == 1 {
+                        "thing"
+                    } else {
+                        "things"
+                    };
+                    println!("Found {} {} monger no longer needs:", garbage.len(), things);
+                }
+
+                let mut removed = Vec::new();
+                let mut reclaimed = 0;
+
+                for found in &garbage {
+                    if !output.is_json() {
+                        println!("    {} ({})", found, format_size(found.size));
+                    }
+
+                    if !fix || !(yes || confirm("    Remove it?")?) {
+                        continue;
+                    }
+
+                    monger.remove_garbage(found)?;
+                    removed.push(&found.path);
+                    reclaimed += found.size;
+                }
+
+                let mut lines = Vec::new();
+
+                if garbage.is_empty() {
+                    lines.push("No leftover files found".to_string());
+                } else if !fix {
+                    lines.push("Run `monger doctor --fix` to remove them".into());
+                } else {
+                    lines.push(format!(
+                        "Removed {} of {}, reclaiming {}",
+                        removed.len(),
+                        garbage.len(),
+                        format_size(reclaimed)
+                    ));
+                }
+
+                if blocking > 0 {
+                    lines.push(format!(
+                        "Found {} problem{} that will stop mongod from running",
+                        blocking,
+                        if blocking == 1 { "" } else { "s" }
+                    ));
+                }
+
+                output.print(
+                    lines.join("\n"),
+                    json!({
+                        "checks": diagnoses,
+                        "blocking": blocking,
+                        "garbage": garbage,
+                        "removed": removed,
+                        "reclaimed": reclaimed,
+                    }),
+                );
+
+                if blocking > 0 {
+                    std::process::exit(1);
+                }
+            }
+            Self::Down { file, purge } => {
+                let file = DeploymentFile::from_file(file)?;
+                let stopped = monger.down(&file, purge)?;
+
+                output.print(
+                    stopped
+                        .iter()
+                        .map(|name| format!("Stopped {}", name))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    json!({ "stopped": stopped }),
+                );
+            }
             Self::Download { url, id, force } => {
-                monger.download_mongodb_version_from_url(&url, &id, force)?;
+                let installation = monger.download_mongodb_version_from_url(&url, &id, force)?;
+                output.print_json(json!({
+                    "id": installation.id,
+                    "downloaded": installation.downloaded,
+                }));
+            }
+            Self::Env { id, shell } => {
+                let path = env::var_os("PATH").unwrap_or_default();
+                let version_env = monger.version_env(&id, &path)?;
+
+                print!(
+                    "{}",
+                    shell.unwrap_or_else(Shell::detect).env_script(&version_env)
+                );
             }
             Self::Get {
-                version,
+                mut versions,
+                from_file,
+                jobs,
                 force,
+                include_prereleases,
                 os,
                 id,
-            } => monger.download_mongodb_version(&version, force, os.as_deref(), id.as_deref())?,
-            Self::List => list(&monger)?,
-            Self::Prune => monger.prune()?,
+            } => {
+                if let Some(path) = from_file {
+                    let contents = std::fs::read_to_string(&path)
+                        .with_context(|| format!("unable to read {}", path.display()))?;
+                    versions.extend(parse_version_list(&contents));
+                }
+
+                if versions.len() > 1 {
+                    if id.is_some() {
+                        return Err(anyhow!(
+                            "--id can only be used when downloading one version"
+                        ));
+                    }
+
+                    let version_strs: Vec<_> = versions.iter().map(String::as_str).collect();
+                    let results = monger.download_mongodb_versions(
+                        &version_strs,
+                        force,
+                        include_prereleases,
+                        os.as_deref(),
+                        jobs,
+                    )?;
+                    let installed = results.iter().filter(|result| result.is_ok()).count();
+                    let mut text = format!("Installed {} of {} versions", installed, results.len());
+
+                    for (version, result) in versions.iter().zip(&results) {
+                        if let Err(e) = result {
+                            let _ = write!(text, "\n    {}: {}", version, e);
+                        }
+                    }
+
+                    output.print(text, installations_json(&versions, &results));
+
+                    failed = installed < results.len();
+                } else {
+                    let installation = match versions.pop() {
+                        Some(version) => monger.download_mongodb_version(
+                            &version,
+                            force,
+                            include_prereleases,
+                            os.as_deref(),
+                            id.as_deref(),
+                        )?,
+                        None => {
+                            let version = monger.resolve_version(None)?;
+
+                            match monger.installed_version(&version) {
+                                Some(installed) if !force => {
+                                    output.print(
+                                        format!(
+                                            "{} is already installed as {}",
+                                            version, installed
+                                        ),
+                                        installation_json(&Installation {
+                                            id: installed.clone(),
+                                            downloaded: false,
+                                        }),
+                                    );
+
+                                    return Ok(());
+                                }
+                                _ => monger.download_mongodb_version(
+                                    &version,
+                                    force,
+                                    include_prereleases,
+                                    os.as_deref(),
+                                    id.as_deref(),
+                                )?,
+                            }
+                        }
+                    };
+
+                    output.print_json(installation_json(&installation));
+                }
+            }
+            Self::Info { id } => {
+                let info = monger.version_info(&id)?;
+                output.print(format_info(&info), info_json(&info)?);
+            }
+            Self::Init { shell } => print!("{}", shell.unwrap_or_else(Shell::detect).init_script()),
+            Self::List { long } => list(&monger, output, long)?,
+            Self::ListRemote { version } => {
+                let versions: Vec<_> = monger
+                    .list_remote_versions(version.as_deref())?
+                    .into_iter()
+                    .map(|(version, kind)| (version.to_string(), kind))
+                    .collect();
+
+                output.print(
+                    format_list(
+                        "available versions",
+                        versions
+                            .iter()
+                            .map(|(version, kind)| format!("{} ({})", version, kind)),
+                    ),
+                    list_remote_json(&versions),
+                );
+            }
+            Self::Prune {
+                dry_run,
+                keep,
+                older_than,
+                include_prereleases,
+            } => {
+                let report = monger.prune(&PrunePolicy {
+                    keep,
+                    older_than,
+                    include_prereleases,
+                    dry_run,
+                })?;
+
+                let (deleted, reclaimed) = if dry_run {
+                    ("Would delete", "Would reclaim")
+                } else {
+                    ("Deleted", "Reclaimed")
+                };
+
+                let mut lines: Vec<_> = report
+                    .pruned
+                    .iter()
+                    .map(|pruned| match (&pruned.newer_version, older_than) {
+                        (Some(newer_version), _) => format!(
+                            "{} {} (because {} is installed)",
+                            deleted, pruned.version, newer_version
+                        ),
+                        (None, Some(older_than)) => format!(
+                            "{} {} (because it was installed more than {} ago)",
+                            deleted,
+                            pruned.version,
+                            humantime::format_duration(older_than)
+                        ),
+                        (None, None) => invariant!("only old custom builds are pruned"),
+                    })
+                    .collect();
+
+                lines.extend(report.protected.iter().map(|protected| {
+                    format!("Kept {} ({})", protected.version, protected.protection)
+                }));
+
+                if report.pruned.is_empty() {
+                    lines.push("Nothing to prune".into());
+                } else {
+                    lines.push(format!("{} {}", reclaimed, format_size(report.reclaimed)));
+                }
+
+                output.print(lines.join("\n"), prune_json(&report, dry_run)?);
+            }
+            Self::Rehash => {
+                let shims = monger.rehash(&env::current_exe()?)?;
+                let dir = monger.shims_dir();
+
+                output.print(
+                    format!("Generated {} shims in {}", shims.len(), dir.display()),
+                    json!({ "dir": dir, "shims": shims }),
+                );
+            }
+            Self::RollingUpgrade { name, to, set_fcv } => {
+                let state = monger.rolling_upgrade(&name, &to, set_fcv)?;
+                output.print(
+                    format!(
+                        "upgraded {} to {}:\n    {}",
+                        state.name, state.version, state.uri
+                    ),
+                    deployment_json(&state),
+                );
+            }
             Self::Run { id, bin, bin_args } => {
+                let (id, bin) = match bin {
+                    Some(bin) => (id, bin),
+                    None if monger.installed_version(&id).is_some() => {
+                        return Err(anyhow!(
+                            "no binary given to run with {}; pass it after the version, e.g. \
+                             `monger run {} mongo`",
+                            id,
+                            id
+                        ));
+                    }
+                    None => (monger.resolve_version(None)?, id),
+                };
+
                 return Err(monger
                     .exec_command(&bin, bin_args.into_iter().map(Into::into).collect(), &id)
                     .into());
@@ -56,51 +619,601 @@ impl Options {
                     .repo_name("monger")
                     .current_version(env!("CARGO_PKG_VERSION"))
                     .bin_name(env!("CARGO_PKG_NAME"))
-                    .show_download_progress(true)
+                    .show_download_progress(!output.is_json())
+                    .no_confirm(output.is_json())
                     .build()?
                     .update()?;
 
+                let json = json!({
+                    "version": status.version(),
+                    "updated": status.updated(),
+                });
+
                 if status.uptodate() {
-                    println!("Already have the latest version");
+                    output.print("Already have the latest version", json);
+                } else {
+                    output.print(
+                        format!("Downloaded and installed {}", status.version()),
+                        json,
+                    );
+                }
+            }
+            Self::Shims(Shims::List) => {
+                let shims = monger.list_shims()?;
+                let pinned = monger.pinned_version()?;
+
+                let text = if shims.is_empty() {
+                    "no shims exist; run `monger rehash` to generate them".to_string()
+                } else {
+                    let header = match pinned {
+                        Some(ref pinned) => {
+                            format!("shims (running {} from {})", pinned.version, pinned.source)
+                        }
+                        None => "shims (no version is pinned)".to_string(),
+                    };
+
+                    format_list(&header, shims.iter())
+                };
+
+                output.print(
+                    text,
+                    json!({
+                        "pinned": pinned.map(|pinned| json!({
+                            "version": pinned.version,
+                            "source": pinned.source.to_string(),
+                        })),
+                        "shims": shims,
+                    }),
+                );
+            }
+            Self::Shims(Shims::Path) => {
+                let dir = monger.shims_dir();
+                output.print(dir.display(), json!({ "dir": dir }));
+            }
+            Self::Status => status(&monger, output)?,
+            Self::Use { id } => {
+                return Err(anyhow!(
+                    "`monger use {}` has to be run through the shell function printed by `monger \
+                     init`; add `eval \"$(monger init)\"` to your shell's startup file, or run \
+                     `eval \"$(monger env {})\"` instead",
+                    id,
+                    id
+                ))
+            }
+            Self::Up { file, recreate } => {
+                let file = DeploymentFile::from_file(file)?;
+                let results = monger.up(&file, recreate)?;
+
+                let text = results
+                    .iter()
+                    .map(|(state, action)| {
+                        format!(
+                            "{} ({}): {}\n    {}",
+                            state.name,
+                            state.topology.kind(),
+                            up_action(action),
+                            state.uri
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                let json = json!({
+                    "deployments": results
+                        .iter()
+                        .map(|(state, action)| {
+                            let mut json = deployment_json(state);
+                            json["action"] = up_action(action).into();
+                            json
+                        })
+                        .collect::<Vec<_>>(),
+                });
+
+                output.print(text, json);
+            }
+            Self::UpgradeData { name, to } => {
+                monger.upgrade_data(&name, &to)?;
+                output.print(
+                    format!("Upgraded data directory {} to {}", name, to),
+                    json!({ "name": name, "version": to }),
+                );
+            }
+            Self::Which { id, binary } => {
+                let path = monger.binary_path(&id, &binary)?;
+                output.print(
+                    path.display(),
+                    json!({ "version": id, "binary": binary, "path": path }),
+                );
+            }
+            Self::Snapshot(Snapshot::Delete { id, name }) => {
+                let deleted = monger.delete_snapshot(&id, &name)?;
+                let json = json!({ "version": id, "name": name, "deleted": deleted });
+
+                if deleted {
+                    output.print(format!("Deleted snapshot {} of {}", name, id), json);
                 } else {
-                    println!("Downloaded and installed {}", status.version());
+                    output.print_json(json);
                 }
             }
-            Self::Start { id, mongod_args } => {
-                monger.start_mongod(
-                    mongod_args.into_iter().map(Into::into).collect(),
-                    &id,
-                    true,
-                )?;
+            Self::Snapshot(Snapshot::List { id }) => {
+                let snapshots = monger.list_snapshots(id.as_deref())?;
+
+                output.print(
+                    format_list(
+                        "snapshots",
+                        snapshots.iter().map(|snapshot| {
+                            format!(
+                                "{} ({}, {})",
+                                snapshot.name,
+                                snapshot.version,
+                                format_size(snapshot.size)
+                            )
+                        }),
+                    ),
+                    json!({
+                        "snapshots": snapshots
+                            .iter()
+                            .map(|snapshot| {
+                                json!({
+                                    "name": snapshot.name,
+                                    "version": snapshot.version,
+                                    "size": snapshot.size,
+                                })
+                            })
+                            .collect::<Vec<_>>(),
+                    }),
+                );
+            }
+            Self::Snapshot(Snapshot::Restore { id, name }) => {
+                monger.restore_snapshot(&id, &name)?;
+                output.print(
+                    format!("Restored snapshot {} of {}", name, id),
+                    json!({ "version": id, "name": name, "restored": true }),
+                );
+            }
+            Self::Snapshot(Snapshot::Save { id, name, force }) => {
+                let snapshot = monger.save_snapshot(&id, &name, force)?;
+                output.print(
+                    format!(
+                        "Saved snapshot {} of {} ({})",
+                        snapshot.name,
+                        snapshot.version,
+                        format_size(snapshot.size)
+                    ),
+                    json!({
+                        "name": snapshot.name,
+                        "version": snapshot.version,
+                        "size": snapshot.size,
+                    }),
+                );
             }
+            Self::Start {
+                id,
+                data,
+                force,
+                security,
+                mongod_args,
+            } => {
+                let id = monger.resolve_version(id.as_deref())?;
+                let mut args = mongod_args.into_iter().map(Into::into).collect();
+                let options = SecurityOptions {
+                    auth: security.auth(),
+                    tls: security.tls,
+                };
+
+                let mut uri = None;
+
+                if options.auth.is_some() || options.tls {
+                    let (secure_args, secure_uri) =
+                        monger.secure_mongod_args(args, &id, data.as_deref(), &options)?;
+                    args = secure_args;
+                    uri = Some(secure_uri);
+                }
+
+                // Nothing is printed until mongod is about to start, so that a failure leaves
+                // only the error on stdout.
+                let command = monger.prepare_mongod(args, &id, data.as_deref(), force)?;
+
+                match uri {
+                    Some(uri) => output.print(
+                        format!("connect with:\n    {}", uri),
+                        json!({ "version": id, "uri": uri }),
+                    ),
+                    None => output.print_json(json!({ "version": id })),
+                }
+
+                // The JSON document is the only thing printed to stdout, so mongod's log goes to
+                // stderr along with the progress events.
+                if output.is_json() {
+                    monger.set_exec_stdout_to_stderr(true);
+                }
+
+                return Err(monger.exec_mongod(command).into());
+            }
+        }
+
+        if changes_versions {
+            monger.refresh_shims(&env::current_exe()?)?;
+        }
+
+        if failed {
+            std::process::exit(1);
         }
 
         Ok(())
     }
 }
 
-fn list(monger: &Monger) -> Result<()> {
+/// Runs the binary that monger was invoked as if it was invoked through a shim (i.e. a symlink in
+/// the shims directory named after the binary). Returns `None` if it wasn't.
+pub(super) fn run_shim() -> Option<anyhow::Error> {
+    let mut args = env::args_os();
+    let binary = Path::new(&args.next()?)
+        .file_name()?
+        .to_string_lossy()
+        .into_owned();
+
+    if binary == env!("CARGO_PKG_NAME") {
+        return None;
+    }
+
+    let monger = match Monger::new() {
+        Ok(monger) => monger,
+        Err(e) => return Some(e.into()),
+    };
+
+    if !monger.is_shim(&binary) {
+        return None;
+    }
+
+    Some(monger.exec_shim(&binary, args.collect()).into())
+}
+
+impl Security {
+    fn auth(&self) -> Option<Auth> {
+        match (&self.auth_user, &self.auth_password) {
+            (Some(username), Some(password)) => Some(Auth {
+                username: username.clone(),
+                password: password.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn print_deployments(
+    output: Output,
+    header: &str,
+    deployments: Vec<DeploymentState>,
+    show_members: bool,
+) {
+    let mut lines = Vec::new();
+
+    for deployment in &deployments {
+        let running = deployment
+            .members
+            .iter()
+            .filter(|member| member.is_running())
+            .count();
+
+        lines.push(format!(
+            "{} ({} {}): {} [{}/{} processes running]",
+            deployment.name,
+            deployment.topology.kind(),
+            deployment.version,
+            deployment.uri,
+            running,
+            deployment.members.len()
+        ));
+
+        if show_members {
+            for member in &deployment.members {
+                let status = if member.is_running() {
+                    format!("running (pid {})", member.pid)
+                } else {
+                    "stopped".to_string()
+                };
+
+                lines.push(format!(
+                    "    {} on port {}: {}",
+                    member.name, member.port, status
+                ));
+            }
+        }
+    }
+
+    output.print(
+        format_list(header, lines.iter()),
+        json!({ header: deployments.iter().map(deployment_json).collect::<Vec<_>>() }),
+    );
+}
+
+fn list_clusters(monger: &Monger, output: Output) -> Result<()> {
+    let clusters = monger
+        .list_deployments()?
+        .into_iter()
+        .filter(|deployment| matches!(deployment.topology, Topology::Sharded(..)))
+        .collect();
+
+    print_deployments(output, "clusters", clusters, false);
+
+    Ok(())
+}
+
+fn status(monger: &Monger, output: Output) -> Result<()> {
+    print_deployments(output, "deployments", monger.list_deployments()?, true);
+
+    Ok(())
+}
+
+fn list(monger: &Monger, output: Output, long: bool) -> Result<()> {
     let mut versions: Vec<_> = monger
-        .list_versions()?
+        .list_installed(long)?
         .into_iter()
-        .map(|s| s.to_string_lossy().into_owned())
+        .map(|installed| {
+            let build = if installed.is_nightly() {
+                Some("nightly")
+            } else if installed.is_prerelease() {
+                Some("pre-release")
+            } else {
+                None
+            };
+
+            ListedVersion {
+                build,
+                size: installed.size,
+                aliases: monger.aliases_of(&installed.id),
+                default: false,
+                id: installed.id,
+                manifest: installed.manifest,
+            }
+        })
         .collect();
-    versions.sort();
 
-    if file_exists_in_path("mongod") {
-        versions.push("system".to_string());
+    if monger.find_system_binary("mongod").is_some() {
+        versions.push(ListedVersion {
+            aliases: monger.aliases_of("system"),
+            ..ListedVersion::new("system")
+        });
+    }
+
+    if let Some(default) = monger
+        .default_version()
+        .and_then(|id| monger.installed_version(id))
+    {
+        for version in versions.iter_mut().filter(|version| version.id == default) {
+            version.default = true;
+        }
     }
 
-    print!("installed versions:");
+    let labelled: Vec<_> = versions
+        .iter()
+        .map(|version| {
+            let mut labels = version.aliases.clone();
 
-    if versions.is_empty() {
-        println!(" none");
+            if version.default {
+                labels.insert(0, "default");
+            }
+
+            if let Some(build) = version.build {
+                labels.insert(0, build);
+            }
+
+            if labels.is_empty() {
+                version.id.clone()
+            } else {
+                format!("{} ({})", version.id, labels.join(", "))
+            }
+        })
+        .collect();
+
+    let text = if long && !versions.is_empty() {
+        let rows = versions
+            .iter()
+            .zip(&labelled)
+            .map(|(version, label)| {
+                let field = |f: fn(&Manifest) -> Option<&str>| {
+                    version
+                        .manifest
+                        .as_ref()
+                        .and_then(f)
+                        .unwrap_or("-")
+                        .to_string()
+                };
+
+                vec![
+                    label.clone(),
+                    field(|m| m.version.as_deref()),
+                    field(|m| m.edition.as_deref()),
+                    match version.manifest {
+                        Some(Manifest {
+                            os: Some(ref os),
+                            arch: Some(ref arch),
+                            ..
+                        }) => format!("{} {}", os, arch),
+                        _ => "-".to_string(),
+                    },
+                    version
+                        .size
+                        .map(format_size)
+                        .unwrap_or_else(|| "-".to_string()),
+                    field(|m| Some(&m.installed_at)),
+                    if version.id == "system" {
+                        "PATH".to_string()
+                    } else {
+                        field(|m| Some(&m.url))
+                    },
+                ]
+            })
+            .collect();
+
+        format_table(
+            &[
+                "ID",
+                "VERSION",
+                "EDITION",
+                "PLATFORM",
+                "SIZE",
+                "INSTALLED",
+                "SOURCE",
+            ],
+            rows,
+        )
     } else {
-        println!();
+        format_list("installed versions", labelled.iter())
+    };
+
+    output.print(text, list_json(&versions));
+
+    Ok(())
+}
+
+fn format_info(info: &VersionInfo) -> String {
+    let mut text = format!("{}:", info.id);
+    let mut field = |name: &str, value: &str| {
+        let _ = write!(text, "\n    {:<16}{}", format!("{}:", name), value);
+    };
+
+    if let Some(kind) = info.kind {
+        field("release", &kind.to_string());
+    }
+
+    if let Some(ref manifest) = info.manifest {
+        field("source", &manifest.url);
 
-        for version in versions {
-            println!("    {}", version);
+        if let (Some(os), Some(arch)) = (&manifest.os, &manifest.arch) {
+            field("platform", &format!("{} {}", os, arch));
         }
+
+        field("edition", manifest.edition.as_deref().unwrap_or("-"));
+        field("sha256", &manifest.sha256);
+        field("installed", &manifest.installed_at);
     }
-    Ok(())
+
+    match info.build_info {
+        Some(ref build) => {
+            let unknown = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".into());
+
+            field("version", &unknown(&build.version));
+            field("git version", &unknown(&build.git_version));
+            field("OpenSSL", &unknown(&build.openssl_version));
+            field("allocator", &unknown(&build.allocator));
+
+            if build.modules.is_empty() {
+                field("modules", "none");
+            } else {
+                field("modules", &build.modules.join(", "));
+            }
+        }
+        None => field("build info", "unknown (unable to run mongod --version)"),
+    }
+
+    field("size", &format_size(info.size));
+    field("data size", &format_size(info.data_size));
+    field("bin dir", &info.bin_dir.to_string_lossy());
+
+    text.push_str(&format_list(
+        "\n    binaries",
+        info.binaries
+            .iter()
+            .map(|binary| format!("    {} ({})", binary.name, format_size(binary.size))),
+    ));
+
+    text
+}
+
+/// Formats rows as columns aligned under a header.
+fn format_table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<_> = headers.iter().map(|header| header.len()).collect();
+
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let headers = headers.iter().map(ToString::to_string).collect();
+
+    std::iter::once(headers)
+        .chain(rows)
+        .map(|row: Vec<String>| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats a header followed by an indented line for each item, or "none" if there are none.
+fn format_list(header: &str, items: impl Iterator<Item = impl AsRef<str>>) -> String {
+    let mut text = format!("{}:", header);
+    let mut empty = true;
+
+    for item in items {
+        let _ = write!(text, "\n    {}", item.as_ref());
+        empty = false;
+    }
+
+    if empty {
+        text.push_str(" none");
+    }
+
+    text
+}
+
+fn up_action(action: &UpAction) -> &'static str {
+    match action {
+        UpAction::Unchanged => "already running",
+        UpAction::Restarted => "restarted",
+        UpAction::Started => "started",
+        UpAction::Recreated => "recreated",
+    }
+}
+
+/// Joins arguments with spaces, quoting the ones that contain whitespace.
+fn format_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if arg.contains(char::is_whitespace) {
+                format!("{:?}", arg)
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Asks a yes-or-no question on stdin, defaulting to no.
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
 }
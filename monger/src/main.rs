@@ -4,14 +4,42 @@
 #[macro_use]
 mod util;
 mod dispatch;
+mod output;
+mod shell;
+
+use std::{path::PathBuf, time::Duration};
 
 use anyhow::Result;
 use monger_core::os::OS_NAMES;
 use structopt::StructOpt;
 
+use crate::{
+    output::{Output, OutputFormat, OUTPUT_FORMATS},
+    shell::{Shell, SHELL_NAMES},
+};
+
 #[derive(Debug, StructOpt)]
 #[structopt(about, author)]
+struct Cli {
+    /// the format to print results in; with json, each command prints a single JSON document to
+    /// stdout and reports progress on stderr as JSON lines
+    #[structopt(
+        long,
+        global = true,
+        default_value = "text",
+        possible_values(OUTPUT_FORMATS)
+    )]
+    output: OutputFormat,
+
+    #[structopt(subcommand)]
+    command: Options,
+}
+
+#[derive(Debug, StructOpt)]
 enum Options {
+    /// manages names for MongoDB versions that can be used in place of their IDs
+    Alias(Alias),
+
     /// clear the database files for an installed MongoDB version
     Clear {
         /// the ID of the MongoDB version whose files should be cleared
@@ -19,6 +47,12 @@ enum Options {
         id: String,
     },
 
+    /// manages local sharded clusters
+    Cluster(Cluster),
+
+    /// gets or sets monger's settings, which are stored in ~/.monger/config.toml
+    Config(Config),
+
     /// deletes an installed MongoDB version
     Delete {
         /// the ID of the MongoDB version to delete
@@ -26,9 +60,50 @@ enum Options {
         id: String,
     },
 
+    /// manages the data directories that store the database files of started mongods
+    Data(Data),
+
+    /// prints or sets the MongoDB version used when none is given and none is pinned by
+    /// $MONGER_VERSION or the current project
+    Default {
+        /// the ID of the MongoDB version to use by default
+        #[structopt(name = "ID")]
+        id: Option<String>,
+    },
+
     /// manages the default arguments used when starting a mongod
     Defaults(Defaults),
 
+    /// checks whether this machine can run a MongoDB version, and finds files and directories
+    /// that monger left behind and no longer needs, such as the database files of deleted versions
+    /// and the leftovers of failed installations; exits with an error if a problem would stop
+    /// mongod from running
+    Doctor {
+        /// the ID of the MongoDB version to check; defaults to the version that would be used
+        /// when none is given
+        #[structopt(name = "ID")]
+        id: Option<String>,
+
+        /// remove what was found, asking for confirmation first
+        #[structopt(long)]
+        fix: bool,
+
+        /// remove what was found without asking for confirmation
+        #[structopt(long, short, requires = "fix")]
+        yes: bool,
+    },
+
+    /// stops the deployments described in a topology file
+    Down {
+        /// the TOML file describing the deployments
+        #[structopt(long, short, parse(from_os_str), default_value = "monger.toml")]
+        file: PathBuf,
+
+        /// delete the data of the deployments as well
+        #[structopt(long)]
+        purge: bool,
+    },
+
     /// downloads a MongoDB version from a given URL
     Download {
         /// the URL to download from
@@ -44,42 +119,140 @@ enum Options {
         force: bool,
     },
 
-    /// downloads a MongoDB version
+    /// prints shell code that puts the binaries of a MongoDB version first on the PATH
+    Env {
+        /// the ID of the MongoDB version
+        #[structopt(name = "ID")]
+        id: String,
+
+        /// the shell to print code for; defaults to the one in $SHELL
+        #[structopt(long, possible_values(SHELL_NAMES))]
+        shell: Option<Shell>,
+    },
+
+    /// downloads one or more MongoDB versions
+    #[structopt(alias = "install")]
     Get {
-        /// the MongoDB version to download
+        /// the MongoDB versions to download, each either an exact version, a major.minor version, a
+        /// constraint like '>=4.4, <5', '~5.0', or '^6', one of latest, latest-dev, lts, and rapid,
+        /// or a nightly build (nightly or e.g. 8.0-nightly); defaults to the version pinned by the
+        /// current project in a .monger-version file
         #[structopt(name = "VERSION")]
-        version: String,
+        versions: Vec<String>,
+
+        /// also download the versions listed in a file, separated by whitespace or newlines; text
+        /// after a '#' is ignored
+        #[structopt(long, name = "PATH")]
+        from_file: Option<PathBuf>,
+
+        /// how many versions to download at once when downloading several; defaults to
+        /// download.jobs from the config, or 4
+        #[structopt(long, short)]
+        jobs: Option<usize>,
 
         /// download the MongoDB version even if it already is installed
         #[structopt(long, short)]
         force: bool,
 
+        /// let major.minor versions and constraints resolve to release candidates and other
+        /// pre-releases, e.g. to test against 8.0 before it's released
+        #[structopt(long)]
+        include_prereleases: bool,
+
         /// the OS version to download
         #[structopt(long, possible_values(&OS_NAMES))]
         os: Option<String>,
 
         /// specify a unique identifier for the MongoDB version being downloaded; if not specified,
-        /// it will default to the version string (i,e, 'x.y.z')
+        /// it will default to the version string (i,e, 'x.y.z'); only allowed when downloading a
+        /// single version
         #[structopt(long)]
         id: Option<String>,
     },
 
+    /// shows the binaries, build info, disk usage, and install manifest of an installed MongoDB
+    /// version
+    Info {
+        /// the ID of the MongoDB version
+        #[structopt(name = "ID")]
+        id: String,
+    },
+
+    /// prints shell code that enables `monger use`, to be evaluated in the shell's startup file
+    Init {
+        /// the shell to print code for; defaults to the one in $SHELL
+        #[structopt(name = "SHELL", possible_values(SHELL_NAMES))]
+        shell: Option<Shell>,
+    },
+
     /// lists installed MongoDB versions
-    List,
+    List {
+        /// show where each version was downloaded from, what it was built for, its size, and when
+        /// it was installed
+        #[structopt(long, short)]
+        long: bool,
+    },
+
+    /// lists the MongoDB versions available to download, newest first, with the kind of each
+    /// release (stable, lts, rapid, development, or pre-release)
+    ListRemote {
+        /// only list the releases matching this major.minor version or constraint (e.g. 4.4 or
+        /// '>=5, <7')
+        #[structopt(name = "VERSION")]
+        version: Option<String>,
+    },
 
     /// deletes versions of MongoDB where a newer stable version of the same minor version is
-    /// installed
-    Prune,
+    /// installed; versions that are aliased, set as the default, pinned, or running are kept
+    Prune {
+        /// list the versions that would be deleted without deleting them
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// the number of the newest releases of each major.minor version to keep
+        #[structopt(long, default_value = "1")]
+        keep: usize,
+
+        /// only delete versions installed longer ago than this, e.g. "30days" or "2weeks"
+        #[structopt(long, parse(try_from_str = humantime::parse_duration))]
+        older_than: Option<Duration>,
+
+        /// treat release candidates like other releases, so that the newest ones are kept and the
+        /// rest deleted; by default, release candidates newer than the newest stable release are
+        /// kept
+        #[structopt(long)]
+        include_prereleases: bool,
+    },
+
+    /// regenerates the shims for the binaries of every installed version
+    Rehash,
+
+    /// upgrades a running replica set started by monger to another version one member at a time,
+    /// the way it would be done in production
+    RollingUpgrade {
+        /// the name of the replica set
+        #[structopt(name = "NAME")]
+        name: String,
+
+        /// the ID of the MongoDB version to upgrade to
+        #[structopt(long)]
+        to: String,
+
+        /// set the featureCompatibilityVersion to the new version once every member is upgraded
+        #[structopt(long)]
+        set_fcv: bool,
+    },
 
     /// run a binary of a downloaded MongoDB version
     Run {
-        /// the ID of the MongoDB version of the binary being run
+        /// the ID of the MongoDB version of the binary being run; may be omitted to use the
+        /// version pinned by the current project, in which case the binary is given first
         #[structopt(name = "ID")]
         id: String,
 
         /// the MongoDB binary to run
         #[structopt(name = "BIN")]
-        bin: String,
+        bin: Option<String>,
 
         /// arguments for the MongoDB binary being run
         #[structopt(name = "BIN_ARGS", last(true))]
@@ -89,33 +262,349 @@ enum Options {
     /// updates monger to the latest version
     SelfUpdate,
 
+    /// manages snapshots of the database files of installed MongoDB versions
+    Snapshot(Snapshot),
+
+    /// manages the shims that run the binaries of the pinned version when put on the PATH
+    Shims(Shims),
+
+    /// shows the deployments started by monger and which of their processes are running
+    Status,
+
     /// start an installed mongod
     Start {
-        /// the ID of the mongod version to start
+        /// the ID of the mongod version to start; defaults to the version pinned by the current
+        /// project in a .monger-version file
         #[structopt(name = "ID")]
-        id: String,
+        id: Option<String>,
+
+        /// the name of the data directory to store the database files in; defaults to one named
+        /// after the version
+        #[structopt(long)]
+        data: Option<String>,
+
+        /// start the mongod even if the data directory was last used by a version that it can't
+        /// be directly upgraded or downgraded from
+        #[structopt(long, short)]
+        force: bool,
+
+        #[structopt(flatten)]
+        security: Security,
 
         /// extra arguments for the mongod being run
         #[structopt(name = "MONGODB_ARGS", last(true))]
         mongod_args: Vec<String>,
     },
+
+    /// puts the binaries of a MongoDB version first on the PATH of the current shell; requires the
+    /// code printed by `monger init` to be evaluated by the shell first
+    Use {
+        /// the ID of the MongoDB version
+        #[structopt(name = "ID")]
+        id: String,
+    },
+
+    /// starts the deployments described in a topology file, or brings existing ones back up
+    Up {
+        /// the TOML file describing the deployments
+        #[structopt(long, short, parse(from_os_str), default_value = "monger.toml")]
+        file: PathBuf,
+
+        /// delete and start again any deployment whose topology differs from the file
+        #[structopt(long)]
+        recreate: bool,
+    },
+
+    /// upgrades the database files in a data directory to a newer version, going through each
+    /// intermediate release and setting the featureCompatibilityVersion
+    UpgradeData {
+        /// the name of the data directory
+        #[structopt(name = "NAME")]
+        name: String,
+
+        /// the ID of the MongoDB version to upgrade to
+        #[structopt(long)]
+        to: String,
+    },
+
+    /// prints the absolute path of a binary of a MongoDB version without running it
+    Which {
+        /// the ID of the MongoDB version; aliases, major.minor versions, constraints, and "system"
+        /// are resolved the same way as by `monger run`
+        #[structopt(name = "ID")]
+        id: String,
+
+        /// the name of the binary, e.g. mongod
+        #[structopt(name = "BIN")]
+        binary: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum Alias {
+    /// creates an alias for an installed MongoDB version, or changes the version of an existing
+    /// one
+    Create {
+        /// the name of the alias
+        #[structopt(name = "NAME")]
+        name: String,
+
+        /// the ID of the MongoDB version the alias refers to
+        #[structopt(name = "ID")]
+        id: String,
+    },
+
+    /// deletes an alias
+    Delete {
+        /// the name of the alias
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+
+    /// lists the aliases and the MongoDB versions they refer to
+    List,
+}
+
+#[derive(Debug, StructOpt)]
+enum Cluster {
+    /// lists the clusters started by monger
+    List,
+
+    /// starts a local sharded cluster
+    Start {
+        /// the ID of the MongoDB version to run the cluster with
+        #[structopt(name = "ID", required_unless = "file")]
+        id: Option<String>,
+
+        /// a TOML file describing the cluster topology; if specified, the other topology options
+        /// are ignored
+        #[structopt(long, short, parse(from_os_str))]
+        file: Option<PathBuf>,
+
+        /// the name of the cluster
+        #[structopt(long, default_value = "cluster")]
+        name: String,
+
+        /// the first port to use; the mongos routers, config servers and shards are assigned
+        /// consecutive ports starting from this one
+        #[structopt(long, default_value = "27017")]
+        port: u16,
+
+        /// the number of mongos routers to start
+        #[structopt(long, default_value = "1")]
+        mongos: u16,
+
+        /// the number of members in the config server replica set
+        #[structopt(long, default_value = "1")]
+        config_servers: u16,
+
+        /// the number of shards to start
+        #[structopt(long, default_value = "2")]
+        shards: u16,
+
+        /// the number of members in each shard replica set
+        #[structopt(long, default_value = "1")]
+        shard_members: u16,
+
+        /// start each shard as a standalone mongod rather than a replica set
+        #[structopt(long, conflicts_with = "shard-members")]
+        standalone_shards: bool,
+
+        #[structopt(flatten)]
+        security: Security,
+
+        /// extra arguments for each mongod in the cluster
+        #[structopt(name = "MONGODB_ARGS", last(true))]
+        mongod_args: Vec<String>,
+    },
+
+    /// stops a cluster and deletes its data
+    Stop {
+        /// the name of the cluster to stop
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum Shims {
+    /// lists the shims and the version they currently run
+    List,
+
+    /// prints the directory containing the shims, to be added to the front of the PATH
+    Path,
+}
+
+#[derive(Debug, StructOpt)]
+enum Snapshot {
+    /// deletes a snapshot
+    Delete {
+        /// the ID of the MongoDB version the snapshot was taken of
+        #[structopt(name = "ID")]
+        id: String,
+
+        /// the name of the snapshot
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+
+    /// lists saved snapshots
+    List {
+        /// only list the snapshots of this MongoDB version
+        #[structopt(name = "ID")]
+        id: Option<String>,
+    },
+
+    /// replaces the database files of a MongoDB version with a snapshot
+    Restore {
+        /// the ID of the MongoDB version whose files should be replaced
+        #[structopt(name = "ID")]
+        id: String,
+
+        /// the name of the snapshot
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+
+    /// saves a snapshot of the database files of a MongoDB version
+    Save {
+        /// the ID of the MongoDB version whose files should be saved
+        #[structopt(name = "ID")]
+        id: String,
+
+        /// the name to save the snapshot as
+        #[structopt(name = "NAME")]
+        name: String,
+
+        /// overwrite an existing snapshot with the same name
+        #[structopt(long, short)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+struct Security {
+    /// create an admin user with this name and require clients to authenticate
+    #[structopt(long, requires = "auth-password")]
+    auth_user: Option<String>,
+
+    /// the password of the admin user
+    #[structopt(long, requires = "auth-user")]
+    auth_password: Option<String>,
+
+    /// require clients to connect over TLS using certificates generated by monger
+    #[structopt(long)]
+    tls: bool,
+}
+
+#[derive(Debug, StructOpt)]
+enum Config {
+    /// prints the value of a setting
+    Get {
+        /// the setting to print, e.g. download.mirror
+        #[structopt(name = "KEY")]
+        key: String,
+    },
+
+    /// lists the settings that are set
+    List,
+
+    /// changes the value of a setting
+    Set {
+        /// the setting to change, e.g. download.mirror
+        #[structopt(name = "KEY")]
+        key: String,
+
+        /// the new value, parsed as TOML if possible (e.g. 5 or ["--quiet"]) and as a string
+        /// otherwise
+        #[structopt(name = "VALUE")]
+        value: String,
+    },
+
+    /// restores a setting to its default
+    Unset {
+        /// the setting to restore
+        #[structopt(name = "KEY")]
+        key: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum Data {
+    /// deletes the database files in a data directory, keeping the empty directory
+    Clear {
+        /// the name of the data directory
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+
+    /// deletes a data directory
+    Delete {
+        /// the name of the data directory
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+
+    /// lists the data directories and the version that last used each of them
+    List,
 }
 
 #[derive(Debug, StructOpt)]
 enum Defaults {
     /// clears the previously set default arguments
-    Clear,
+    Clear {
+        /// clear the default arguments of a major.minor release or version ID instead of the
+        /// global ones
+        #[structopt(long = "for", name = "ID")]
+        scope: Option<String>,
+    },
 
     /// prints the default arguments used when starting a mongod
-    Get,
+    Get {
+        /// print the default arguments of a major.minor release or version ID instead of the
+        /// global ones
+        #[structopt(long = "for", name = "ID")]
+        scope: Option<String>,
+    },
+
+    /// lists the default arguments of every scope
+    List,
 
     /// sets the default arguments used when starting a mongod
+    ///
+    /// Arguments set for a version ID take precedence over those set for its major.minor release,
+    /// which take precedence over the global ones.
     Set {
+        /// set the default arguments of a major.minor release (e.g. 4.4) or version ID instead of
+        /// the global ones
+        #[structopt(long = "for", name = "ID")]
+        scope: Option<String>,
+
         #[structopt(name = "ARGS", last(true))]
         args: Vec<String>,
     },
+
+    /// prints the default arguments that a mongod of a version would be started with
+    Show {
+        /// the ID of the MongoDB version
+        #[structopt(name = "ID")]
+        id: String,
+    },
 }
 
 fn main() -> Result<()> {
-    Options::from_args().dispatch()
+    if let Some(error) = dispatch::run_shim() {
+        return Err(error);
+    }
+
+    let cli = Cli::from_args();
+    let output = Output::new(cli.output);
+
+    match cli.command.dispatch(output) {
+        Err(error) if output.is_json() => {
+            output.print_error(&error);
+            std::process::exit(1);
+        }
+        result => result,
+    }
 }
@@ -0,0 +1,428 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use monger_core::{
+    deployment::{DeploymentState, Member},
+    error, Event, Installation, Manifest, PruneReport, ReleaseKind, VersionInfo,
+};
+use serde_json::{json, Value};
+
+pub const OUTPUT_FORMATS: &[&str] = &["json", "text"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Text,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "text" => Ok(OutputFormat::Text),
+            other => Err(format!("unsupported output format: {}", other)),
+        }
+    }
+}
+
+/// Prints the results of commands in the format chosen with `--output`.
+///
+/// In text mode, results and progress are printed to stdout for people to read. In JSON mode,
+/// each command prints a single JSON document to stdout, and progress is printed to stderr as one
+/// JSON object per line so that it doesn't get mixed up with the result.
+#[derive(Clone, Copy, Debug)]
+pub struct Output {
+    format: OutputFormat,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    pub fn is_json(self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Prints the text in text mode (unless it's empty), or the JSON document in JSON mode.
+    pub fn print(self, text: impl Display, json: Value) {
+        match self.format {
+            OutputFormat::Json => println!("{}", json),
+            OutputFormat::Text => {
+                let text = text.to_string();
+
+                if !text.is_empty() {
+                    println!("{}", text);
+                }
+            }
+        }
+    }
+
+    /// Prints the JSON document in JSON mode, and nothing in text mode.
+    pub fn print_json(self, json: Value) {
+        if self.is_json() {
+            println!("{}", json);
+        }
+    }
+
+    /// Reports progress from monger-core.
+    pub fn event(self, event: &Event) {
+        match self.format {
+            OutputFormat::Json => {
+                if let Ok(line) = serde_json::to_string(event) {
+                    let _ = writeln!(io::stderr(), "{}", line);
+                }
+            }
+            OutputFormat::Text => println!("{}", event),
+        }
+    }
+
+    /// Prints an error that made a command fail as a JSON document.
+    pub fn print_error(self, error: &anyhow::Error) {
+        println!("{}", json!({ "error": format!("{:#}", error) }));
+    }
+}
+
+pub fn deployment_json(deployment: &DeploymentState) -> Value {
+    json!({
+        "name": deployment.name,
+        "kind": deployment.topology.kind(),
+        "version": deployment.version,
+        "uri": deployment.uri,
+        "members": deployment.members.iter().map(member_json).collect::<Vec<_>>(),
+    })
+}
+
+fn member_json(member: &Member) -> Value {
+    json!({
+        "name": member.name,
+        "port": member.port,
+        "pid": member.pid,
+        "running": member.is_running(),
+    })
+}
+
+/// A version shown by `monger list`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListedVersion<'a> {
+    pub id: String,
+    pub manifest: Option<Manifest>,
+
+    /// The disk space used by the version's files in bytes; only measured for `monger list --long`,
+    /// and unknown for the system version.
+    pub size: Option<u64>,
+
+    /// "nightly" or "pre-release" for builds that aren't releases.
+    pub build: Option<&'static str>,
+
+    pub aliases: Vec<&'a str>,
+    pub default: bool,
+}
+
+impl ListedVersion<'_> {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.into(),
+            manifest: None,
+            size: None,
+            build: None,
+            aliases: Vec::new(),
+            default: false,
+        }
+    }
+}
+
+pub fn list_json(versions: &[ListedVersion]) -> Value {
+    json!({
+        "versions": versions
+            .iter()
+            .map(|version| {
+                json!({
+                    "id": version.id,
+                    "prerelease": version.build == Some("pre-release"),
+                    "nightly": version.build == Some("nightly"),
+                    "aliases": version.aliases,
+                    "default": version.default,
+                    "size": version.size,
+                    "manifest": version.manifest,
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// The releases listed by `monger list-remote`, with the kind of each. Only releases are listed, so
+/// unlike `monger list` there are no nightly builds to mark.
+pub fn list_remote_json(versions: &[(String, ReleaseKind)]) -> Value {
+    json!({
+        "versions": versions
+            .iter()
+            .map(|(version, kind)| {
+                json!({
+                    "version": version,
+                    "kind": kind,
+                    "prerelease": *kind == ReleaseKind::Prerelease,
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+pub fn installation_json(installation: &Installation) -> Value {
+    json!({
+        "id": installation.id,
+        "downloaded": installation.downloaded,
+    })
+}
+
+/// The result of installing several versions with `monger get`, given in the same order as the
+/// versions.
+pub fn installations_json(versions: &[String], results: &[error::Result<Installation>]) -> Value {
+    let installed = results.iter().filter(|result| result.is_ok()).count();
+
+    json!({
+        "versions": versions
+            .iter()
+            .zip(results)
+            .map(|(version, result)| match result {
+                Ok(installation) => json!({
+                    "version": version,
+                    "id": installation.id,
+                    "downloaded": installation.downloaded,
+                }),
+                Err(e) => json!({ "version": version, "error": e.to_string() }),
+            })
+            .collect::<Vec<_>>(),
+        "installed": installed,
+        "failed": results.len() - installed,
+    })
+}
+
+pub fn info_json(info: &VersionInfo) -> serde_json::Result<Value> {
+    serde_json::to_value(info)
+}
+
+pub fn prune_json(report: &PruneReport, dry_run: bool) -> serde_json::Result<Value> {
+    let mut json = serde_json::to_value(report)?;
+    json["dry_run"] = dry_run.into();
+
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use monger_core::{
+        error::Error, BinaryInfo, Installation, Manifest, ProtectedVersion, Protection,
+        PruneReport, PrunedVersion, ReleaseKind, VersionInfo,
+    };
+    use serde_json::json;
+
+    use super::{
+        info_json, installation_json, installations_json, list_json, list_remote_json, prune_json,
+        ListedVersion,
+    };
+
+    fn manifest() -> Manifest {
+        Manifest {
+            id: "4.4.1".into(),
+            version: Some("4.4.1".into()),
+            nightly: None,
+            url: "https://fastdl.mongodb.org/linux/mongodb-linux-x86_64-ubuntu1804-4.4.1.tgz"
+                .into(),
+            os: Some("ubuntu1804".into()),
+            arch: Some("x86_64".into()),
+            edition: Some("community".into()),
+            kind: Some(ReleaseKind::Stable),
+            sha256: "ab12".into(),
+            installed_at: "2020-10-01T12:00:00Z".into(),
+        }
+    }
+
+    fn manifest_json() -> serde_json::Value {
+        json!({
+            "id": "4.4.1",
+            "version": "4.4.1",
+            "url": "https://fastdl.mongodb.org/linux/mongodb-linux-x86_64-ubuntu1804-4.4.1.tgz",
+            "os": "ubuntu1804",
+            "arch": "x86_64",
+            "edition": "community",
+            "kind": "stable",
+            "sha256": "ab12",
+            "installed_at": "2020-10-01T12:00:00Z",
+        })
+    }
+
+    #[test]
+    fn list() {
+        let versions = vec![
+            ListedVersion {
+                manifest: Some(manifest()),
+                size: Some(1024),
+                aliases: vec!["prod"],
+                default: true,
+                ..ListedVersion::new("4.4.1")
+            },
+            ListedVersion {
+                build: Some("pre-release"),
+                ..ListedVersion::new("7.0.0-rc1")
+            },
+            ListedVersion::new("system"),
+        ];
+
+        assert_eq!(
+            json!({
+                "versions": [
+                    {
+                        "id": "4.4.1",
+                        "prerelease": false,
+                        "nightly": false,
+                        "aliases": ["prod"],
+                        "default": true,
+                        "size": 1024,
+                        "manifest": manifest_json(),
+                    },
+                    {
+                        "id": "7.0.0-rc1",
+                        "prerelease": true,
+                        "nightly": false,
+                        "aliases": [],
+                        "default": false,
+                        "size": null,
+                        "manifest": null,
+                    },
+                    {
+                        "id": "system",
+                        "prerelease": false,
+                        "nightly": false,
+                        "aliases": [],
+                        "default": false,
+                        "size": null,
+                        "manifest": null,
+                    },
+                ],
+            }),
+            list_json(&versions)
+        );
+    }
+
+    #[test]
+    fn list_remote() {
+        let versions = vec![
+            ("7.0.0-rc1".to_string(), ReleaseKind::Prerelease),
+            ("6.1.0".to_string(), ReleaseKind::Rapid),
+            ("6.0.2".to_string(), ReleaseKind::Lts),
+        ];
+
+        assert_eq!(
+            json!({
+                "versions": [
+                    { "version": "7.0.0-rc1", "kind": "prerelease", "prerelease": true },
+                    { "version": "6.1.0", "kind": "rapid", "prerelease": false },
+                    { "version": "6.0.2", "kind": "lts", "prerelease": false },
+                ],
+            }),
+            list_remote_json(&versions)
+        );
+    }
+
+    #[test]
+    fn get() {
+        assert_eq!(
+            json!({ "id": "4.4.1", "downloaded": true }),
+            installation_json(&Installation {
+                id: "4.4.1".into(),
+                downloaded: true,
+            })
+        );
+
+        let versions = vec!["4.4".to_string(), "9.9".to_string()];
+        let results = vec![
+            Ok(Installation {
+                id: "4.4.1".into(),
+                downloaded: false,
+            }),
+            Err(Error::VersionNotFound {
+                version: "9.9".into(),
+            }),
+        ];
+
+        assert_eq!(
+            json!({
+                "versions": [
+                    { "version": "4.4", "id": "4.4.1", "downloaded": false },
+                    {
+                        "version": "9.9",
+                        "error": Error::VersionNotFound { version: "9.9".into() }.to_string(),
+                    },
+                ],
+                "installed": 1,
+                "failed": 1,
+            }),
+            installations_json(&versions, &results)
+        );
+    }
+
+    #[test]
+    fn info() {
+        let info = VersionInfo {
+            id: "4.4.1".into(),
+            bin_dir: "/home/user/.monger/mongodb-versions/4.4.1/bin".into(),
+            binaries: vec![BinaryInfo {
+                name: "mongod".into(),
+                size: 2048,
+            }],
+            build_info: None,
+            size: 4096,
+            data_size: 0,
+            manifest: Some(manifest()),
+            kind: Some(ReleaseKind::Stable),
+        };
+
+        assert_eq!(
+            json!({
+                "id": "4.4.1",
+                "bin_dir": "/home/user/.monger/mongodb-versions/4.4.1/bin",
+                "binaries": [{ "name": "mongod", "size": 2048 }],
+                "build_info": null,
+                "size": 4096,
+                "data_size": 0,
+                "manifest": manifest_json(),
+                "kind": "stable",
+            }),
+            info_json(&info).unwrap()
+        );
+    }
+
+    #[test]
+    fn prune() {
+        let report = PruneReport {
+            pruned: vec![PrunedVersion {
+                version: "4.4.0".into(),
+                newer_version: Some("4.4.1".into()),
+                size: 512,
+            }],
+            protected: vec![ProtectedVersion {
+                version: "4.2.7".into(),
+                protection: Protection::Aliased {
+                    aliases: vec!["old".into()],
+                },
+            }],
+            reclaimed: 512,
+        };
+
+        assert_eq!(
+            json!({
+                "pruned": [{ "version": "4.4.0", "newer_version": "4.4.1", "size": 512 }],
+                "protected": [{ "version": "4.2.7", "reason": "aliased", "aliases": ["old"] }],
+                "reclaimed": 512,
+                "dry_run": true,
+            }),
+            prune_json(&report, true).unwrap()
+        );
+    }
+}
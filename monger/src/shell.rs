@@ -0,0 +1,108 @@
+use std::{env, path::Path, str::FromStr};
+
+use monger_core::{VersionEnv, VERSION_ENV_VAR};
+
+pub const SHELL_NAMES: &[&str] = &["bash", "fish", "zsh"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shell {
+    Bash,
+    Fish,
+    Zsh,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "fish" => Ok(Shell::Fish),
+            "zsh" => Ok(Shell::Zsh),
+            other => Err(format!("unsupported shell: {}", other)),
+        }
+    }
+}
+
+impl Shell {
+    /// The shell to print code for when none is given, based on `$SHELL`.
+    pub fn detect() -> Self {
+        env::var_os("SHELL")
+            .and_then(|shell| {
+                Path::new(&shell)
+                    .file_name()
+                    .and_then(|name| name.to_str()?.parse().ok())
+            })
+            .unwrap_or(Shell::Bash)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Fish => "fish",
+            Shell::Zsh => "zsh",
+        }
+    }
+
+    fn quote(self, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("'{}'", value.replace('\'', r"'\''")),
+            Shell::Fish => format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'")),
+        }
+    }
+
+    /// Code that exports the environment of a version when evaluated.
+    pub fn env_script(self, env: &VersionEnv) -> String {
+        let dirs: Vec<_> = env
+            .path
+            .iter()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .collect();
+
+        match self {
+            Shell::Bash | Shell::Zsh => format!(
+                "export PATH={};\nexport {}={};\n",
+                self.quote(&dirs.join(":")),
+                VERSION_ENV_VAR,
+                self.quote(&env.version)
+            ),
+            Shell::Fish => format!(
+                "set -gx PATH {};\nset -gx {} {};\n",
+                dirs.iter()
+                    .map(|dir| self.quote(dir))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                VERSION_ENV_VAR,
+                self.quote(&env.version)
+            ),
+        }
+    }
+
+    /// Code that wraps the `monger` command so that `monger use` changes the environment of the
+    /// current shell, to be evaluated in the shell's startup file.
+    pub fn init_script(self) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!(
+                r#"monger() {{
+    if [ "$1" = "use" ]; then
+        shift
+        eval "$(command monger env --shell {} "$@")"
+    else
+        command monger "$@"
+    fi
+}}
+"#,
+                self.name()
+            ),
+            Shell::Fish => r#"function monger
+    if test "$argv[1]" = use
+        command monger env --shell fish $argv[2..-1] | source
+    else
+        command monger $argv
+    end
+end
+"#
+            .into(),
+        }
+    }
+}